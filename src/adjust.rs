@@ -0,0 +1,154 @@
+use colordata::Color;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// how far `warm:1` moves a color towards orange in OKLab (b, then a)
+const WARM_B: f64 = 0.1;
+const WARM_A: f64 = 0.03;
+
+/// one transform of every color of the palette
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Step {
+    /// multiply the OKLCH chroma by 1 + amount, `desaturate:x` is `saturate:-x`
+    Saturate(f64),
+    /// add to the OKLCH lightness, `dim:x` is `brighten:-x`
+    Brighten(f64),
+    /// rotate the hue by degrees
+    Hue(f64),
+    /// move towards orange, `cool:x` is `warm:-x` and moves towards blue
+    Warm(f64),
+    Grayscale,
+}
+
+impl Step {
+    /// `saturate:0.2`, `desaturate:0.2`, `brighten:0.05`, `dim:0.05`, `hue:30`, `warm:0.5`, `cool:0.5` or `grayscale`
+    pub fn parse(step: &str) -> Result<Self, String> {
+        let (name, amount) = match step.trim().split_once(':') {
+            Some((name, amount)) => match amount.trim().parse::<f64>() {
+                Ok(a) => (name.trim(), Some(a)),
+                Err(_) => return Err(format!("invalid amount in adjustment: {}", step)),
+            },
+            None => (step.trim(), None),
+        };
+        match (name, amount) {
+            ("saturate", Some(a)) => Ok(Step::Saturate(a)),
+            ("desaturate", Some(a)) => Ok(Step::Saturate(-a)),
+            ("brighten", Some(a)) => Ok(Step::Brighten(a)),
+            ("dim", Some(a)) => Ok(Step::Brighten(-a)),
+            ("hue", Some(a)) => Ok(Step::Hue(a)),
+            ("warm", Some(a)) => Ok(Step::Warm(a)),
+            ("cool", Some(a)) => Ok(Step::Warm(-a)),
+            ("grayscale", None) => Ok(Step::Grayscale),
+            _ => Err(format!("unknown adjustment: {}", step)),
+        }
+    }
+
+    /// "desaturate:0.2,warm:0.3" to its steps
+    pub fn parse_list(list: &str) -> Result<Vec<Self>, String> {
+        list.split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(Step::parse)
+            .collect()
+    }
+
+    fn apply(&self, color: &Color) -> Color {
+        let (l, c, h) = colorspace::to_oklch(color);
+        match *self {
+            Step::Saturate(amount) => {
+                colorspace::from_oklch(l, (c * (1.0 + amount)).max(0.0), h, color)
+            }
+            Step::Brighten(amount) => {
+                colorspace::from_oklch((l + amount).clamp(0.0, 1.0), c, h, color)
+            }
+            Step::Hue(degrees) => colorspace::from_oklch(l, c, h + degrees, color),
+            Step::Warm(amount) => {
+                let (l, a, b) = colorspace::to_oklab(color);
+                colorspace::from_oklab(l, a + amount * WARM_A, b + amount * WARM_B, color)
+            }
+            Step::Grayscale => colorspace::from_oklch(l, 0.0, h, color),
+        }
+    }
+}
+
+/// the `[adjust]` section: transforms applied to the palette, in order, before rendering
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Adjust {
+    pub steps: Vec<Step>,
+}
+
+impl Adjust {
+    pub fn from_toml(value: &toml::Value) -> Self {
+        let steps = value
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|s| s.as_str())
+                    .map(|s| Step::parse(s).unwrap_or_else(|e| panic!("{}", e)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Adjust { steps }
+    }
+
+    /// every color of the palette with the steps applied
+    pub fn apply(&self, colors: &Colors) -> Colors {
+        let mut adjusted = *colors;
+        let slots = std::iter::once(&mut adjusted.background)
+            .chain(std::iter::once(&mut adjusted.foreground))
+            .chain(std::iter::once(&mut adjusted.cursor))
+            .chain(adjusted.colors.iter_mut());
+        for slot in slots {
+            for step in &self.steps {
+                *slot = step.apply(slot);
+            }
+        }
+        adjusted
+    }
+}
+
+#[test]
+fn adjust_test() {
+    assert_eq!(
+        Step::parse_list("desaturate:0.5, hue:30,grayscale"),
+        Ok(vec![Step::Saturate(-0.5), Step::Hue(30.0), Step::Grayscale])
+    );
+    assert!(Step::parse("warm").is_err());
+    assert!(Step::parse("grayscale:1").is_err());
+    assert!(Step::parse("sharpen:2").is_err());
+
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let chroma = |color: &Color| colorspace::to_oklch(color).1;
+    let lightness = |color: &Color| colorspace::to_oklch(color).0;
+
+    let gray = Adjust {
+        steps: vec![Step::Grayscale],
+    }
+    .apply(&nord);
+    assert!(gray.colors.iter().all(|c| chroma(c) < 0.01));
+
+    let softer = Adjust {
+        steps: vec![Step::Saturate(-0.5), Step::Brighten(-0.1)],
+    }
+    .apply(&nord);
+    assert!(chroma(&softer.colors[1]) < chroma(&nord.colors[1]) * 0.6);
+    assert!(lightness(&softer.foreground) < lightness(&nord.foreground) - 0.09);
+
+    let warmer = Adjust {
+        steps: vec![Step::Warm(0.5)],
+    }
+    .apply(&nord);
+    assert!(colorspace::to_oklab(&warmer.colors[4]).2 > colorspace::to_oklab(&nord.colors[4]).2);
+
+    let rotated = Adjust {
+        steps: vec![Step::Hue(360.0)],
+    }
+    .apply(&nord);
+    assert!(colorspace::delta_e(&rotated.colors[1], &nord.colors[1]) < 0.01);
+}
@@ -0,0 +1,105 @@
+use crate::config::Config;
+use crate::derive;
+use crate::history;
+
+/// color schemes shipped inside the binary, addressable as `builtin:<name>`
+pub const SCHEMES: [(&str, &str); 15] = [
+    (
+        "catppuccin-latte",
+        include_str!("../assets/schemes/catppuccin-latte.toml"),
+    ),
+    (
+        "catppuccin-mocha",
+        include_str!("../assets/schemes/catppuccin-mocha.toml"),
+    ),
+    ("dracula", include_str!("../assets/schemes/dracula.toml")),
+    (
+        "everforest-dark",
+        include_str!("../assets/schemes/everforest-dark.toml"),
+    ),
+    (
+        "everforest-light",
+        include_str!("../assets/schemes/everforest-light.toml"),
+    ),
+    (
+        "gruvbox-dark",
+        include_str!("../assets/schemes/gruvbox-dark.toml"),
+    ),
+    (
+        "gruvbox-light",
+        include_str!("../assets/schemes/gruvbox-light.toml"),
+    ),
+    ("nord", include_str!("../assets/schemes/nord.toml")),
+    ("one-dark", include_str!("../assets/schemes/one-dark.toml")),
+    (
+        "rose-pine",
+        include_str!("../assets/schemes/rose-pine.toml"),
+    ),
+    (
+        "rose-pine-dawn",
+        include_str!("../assets/schemes/rose-pine-dawn.toml"),
+    ),
+    (
+        "solarized-dark",
+        include_str!("../assets/schemes/solarized-dark.toml"),
+    ),
+    (
+        "solarized-light",
+        include_str!("../assets/schemes/solarized-light.toml"),
+    ),
+    (
+        "tokyonight-day",
+        include_str!("../assets/schemes/tokyonight-day.toml"),
+    ),
+    (
+        "tokyonight-night",
+        include_str!("../assets/schemes/tokyonight-night.toml"),
+    ),
+];
+
+/// the variant picked when a scheme is named without one, eg. `builtin:catppuccin`
+/// names without an entry here fall back to `<name>-dark`
+const DEFAULT_VARIANTS: [(&str, &str); 2] = [
+    ("catppuccin", "catppuccin-mocha"),
+    ("tokyonight", "tokyonight-night"),
+];
+
+/// the document of a built-in scheme
+pub fn find(name: &str) -> Option<&'static str> {
+    let lookup = |n: &str| SCHEMES.iter().find(|(s, _)| *s == n).map(|(_, d)| *d);
+    if let Some(document) = lookup(name) {
+        return Some(document);
+    };
+    match DEFAULT_VARIANTS.iter().find(|(n, _)| *n == name) {
+        Some((_, variant)) => lookup(*variant),
+        None => lookup(format!("{}-dark", name).as_str()),
+    }
+}
+
+/// missing colors are derived with `strategy`, unless the scheme sets `derive` itself
+pub fn load_builtin(name: &str, strategy: derive::Strategy) -> Option<Config> {
+    find(name).map(|document| Config::from_toml_str(document, strategy))
+}
+
+/// the built-in schemes with a row of swatches each
+pub fn print_builtin() {
+    let width = SCHEMES.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    for (name, document) in SCHEMES.iter() {
+        let preview = Config::from(*document)
+            .colors
+            .map(|c| history::swatches(&c))
+            .unwrap_or_default();
+        println!("{:width$}  {}", name, preview, width = width);
+    }
+}
+
+#[test]
+fn builtin_test() {
+    for (name, document) in SCHEMES.iter() {
+        assert!(Config::from(*document).colors.is_some(), "{}", name);
+    }
+    assert_eq!(find("gruvbox"), find("gruvbox-dark"));
+    assert_eq!(find("catppuccin"), find("catppuccin-mocha"));
+    assert!(find("nord").is_some());
+    assert!(find("no-such-scheme").is_none());
+}
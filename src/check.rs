@@ -0,0 +1,320 @@
+use colordata::traits::*;
+use colordata::Color;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// colors closer than this (ΔE in OKLab) are reported as near-duplicates
+const DUPLICATE_DELTA_E: f64 = 0.02;
+/// accents closer than this under a simulated color vision deficiency are reported
+const COLLAPSE_DELTA_E: f64 = 0.05;
+
+/// the result of `colstract check`
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub slots: Vec<SlotReport>,
+    pub duplicates: Vec<Duplicate>,
+    pub collapses: Vec<Collapse>,
+}
+
+/// the contrast of one slot against the background and the foreground
+/// None where the slot is that color itself: the background on the background,
+/// the foreground on the foreground, and a cursor that is the foreground
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotReport {
+    pub slot: String,
+    pub hex: String,
+    pub on_background: Option<f64>,
+    pub on_background_level: Option<Level>,
+    pub on_foreground: Option<f64>,
+    pub on_foreground_level: Option<Level>,
+}
+
+/// two slots that are hard to tell apart
+#[derive(Debug, Clone, Serialize)]
+pub struct Duplicate {
+    pub slots: (String, String),
+    pub delta_e: f64,
+}
+
+/// two accents that look alike with a color vision deficiency
+#[derive(Debug, Clone, Serialize)]
+pub struct Collapse {
+    pub deficiency: Deficiency,
+    pub slots: (String, String),
+    pub delta_e: f64,
+}
+
+/// the WCAG 2 level a contrast ratio reaches for normal text
+/// large text only needs 3.0 for AA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Level {
+    Aaa,
+    Aa,
+    AaLarge,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl Level {
+    pub fn from_ratio(ratio: f64) -> Self {
+        if ratio >= 7.0 {
+            Level::Aaa
+        } else if ratio >= 4.5 {
+            Level::Aa
+        } else if ratio >= 3.0 {
+            Level::AaLarge
+        } else {
+            Level::Fail
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Aaa => "AAA",
+            Level::Aa => "AA",
+            Level::AaLarge => "AA large",
+            Level::Fail => "fail",
+        }
+    }
+}
+
+impl Deficiency {
+    pub const ALL: [Deficiency; 3] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// the simulation matrix in linear RGB at full severity
+    /// see Machado, Oliveira and Fernandes, 2009
+    fn matrix(&self) -> [[f64; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// how `color` looks with this deficiency
+    pub fn simulate(&self, color: &Color) -> Color {
+        let (r, g, b) = colorspace::rgb(color);
+        let linear = [
+            colorspace::to_linear(r),
+            colorspace::to_linear(g),
+            colorspace::to_linear(b),
+        ];
+        let m = self.matrix();
+        let channel = |row: usize| {
+            colorspace::from_linear(
+                m[row][0] * linear[0] + m[row][1] * linear[1] + m[row][2] * linear[2],
+            )
+        };
+        colorspace::from_rgb(channel(0), channel(1), channel(2), color)
+    }
+}
+
+/// every slot with its name, background and foreground first
+fn named_slots(colors: &Colors) -> Vec<(String, Color)> {
+    let mut slots = vec![
+        ("background".to_string(), colors.background),
+        ("foreground".to_string(), colors.foreground),
+        ("cursor".to_string(), colors.cursor),
+    ];
+    for (index, color) in colors.colors.iter().enumerate() {
+        slots.push((format!("color{}", index), *color));
+    }
+    slots
+}
+
+pub fn check(colors: &Colors) -> Report {
+    let slots = named_slots(colors);
+    let slot_reports = slots
+        .iter()
+        .map(|(name, color)| {
+            let is_background = name == "background";
+            let is_foreground = name == "foreground"
+                || (name == "cursor" && color.hex() == colors.foreground.hex());
+            let on_background = match is_background {
+                true => None,
+                false => Some(colorspace::contrast_ratio(color, &colors.background)),
+            };
+            let on_foreground = match is_foreground {
+                true => None,
+                false => Some(colorspace::contrast_ratio(color, &colors.foreground)),
+            };
+            SlotReport {
+                slot: name.clone(),
+                hex: color.hex(),
+                on_background,
+                on_background_level: on_background.map(Level::from_ratio),
+                on_foreground,
+                on_foreground_level: on_foreground.map(Level::from_ratio),
+            }
+        })
+        .collect();
+
+    // the cursor usually doubles the foreground on purpose
+    let mut duplicates = Vec::new();
+    for (i, (name_a, a)) in slots.iter().enumerate() {
+        for (name_b, b) in slots.iter().skip(i + 1) {
+            let delta_e = colorspace::delta_e(a, b);
+            if delta_e < DUPLICATE_DELTA_E && name_a != "cursor" && name_b != "cursor" {
+                duplicates.push(Duplicate {
+                    slots: (name_a.clone(), name_b.clone()),
+                    delta_e,
+                });
+            };
+        }
+    }
+
+    // accents that can be told apart normally but not with the deficiency
+    let mut collapses = Vec::new();
+    for deficiency in Deficiency::ALL.iter() {
+        for accents in [1..7, 9..15].iter() {
+            let indices = accents.clone().collect::<Vec<usize>>();
+            for (position, &i) in indices.iter().enumerate() {
+                for &j in &indices[position + 1..] {
+                    let (a, b) = (&colors.colors[i], &colors.colors[j]);
+                    let delta_e =
+                        colorspace::delta_e(&deficiency.simulate(a), &deficiency.simulate(b));
+                    if delta_e < COLLAPSE_DELTA_E && colorspace::delta_e(a, b) >= COLLAPSE_DELTA_E {
+                        collapses.push(Collapse {
+                            deficiency: *deficiency,
+                            slots: (format!("color{}", i), format!("color{}", j)),
+                            delta_e,
+                        });
+                    };
+                }
+            }
+        }
+    }
+
+    Report {
+        slots: slot_reports,
+        duplicates,
+        collapses,
+    }
+}
+
+/// the ratio and its level, blank when the comparison does not apply
+fn measure_label(ratio: Option<f64>, level: Option<Level>) -> String {
+    let (ratio, level) = match (ratio, level) {
+        (Some(ratio), Some(level)) => (ratio, level),
+        _ => return format!("{:>6} {:<8}", "-", "n/a"),
+    };
+    let label = format!("{:<8}", level.as_str());
+    let label = match level {
+        Level::Aaa | Level::Aa => label.green().to_string(),
+        Level::AaLarge => label.yellow().to_string(),
+        Level::Fail => label.red().to_string(),
+    };
+    format!("{:>6.2} {}", ratio, label)
+}
+
+pub fn print_report(report: &Report) {
+    println!(
+        "    {:<11} {:<8} {:>6} {:<8} {:>6} {:<8}",
+        "slot", "hex", "on bg", "", "on fg", ""
+    );
+    for slot in &report.slots {
+        println!(
+            "\x1b[48;2;{}m  \x1b[0m  {:<11} {:<8} {} {}",
+            Color::from(slot.hex.as_str())
+                .rgb_stripped()
+                .replace(',', ";"),
+            slot.slot,
+            slot.hex,
+            measure_label(slot.on_background, slot.on_background_level),
+            measure_label(slot.on_foreground, slot.on_foreground_level)
+        );
+    }
+    for duplicate in &report.duplicates {
+        println!(
+            "{}",
+            format!(
+                "near-duplicate: {} and {} (ΔE {:.3})",
+                duplicate.slots.0, duplicate.slots.1, duplicate.delta_e
+            )
+            .yellow()
+        );
+    }
+    for collapse in &report.collapses {
+        println!(
+            "{}",
+            format!(
+                "{}: {} and {} look alike (ΔE {:.3})",
+                collapse.deficiency.as_str(),
+                collapse.slots.0,
+                collapse.slots.1,
+                collapse.delta_e
+            )
+            .yellow()
+        );
+    }
+    if report.duplicates.is_empty() && report.collapses.is_empty() {
+        println!("{}", "no near-duplicates or collapsing accents".green());
+    };
+}
+
+#[test]
+fn check_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let report = check(&nord);
+    assert_eq!(report.slots.len(), 19);
+    let color8 = report.slots.iter().find(|s| s.slot == "color8").unwrap();
+    assert_eq!(color8.on_background_level, Some(Level::Fail));
+    // nothing is measured against itself
+    let background = report
+        .slots
+        .iter()
+        .find(|s| s.slot == "background")
+        .unwrap();
+    assert_eq!(background.on_background, None);
+    assert!(background.on_foreground.is_some());
+    let cursor = report.slots.iter().find(|s| s.slot == "cursor").unwrap();
+    assert_eq!(nord.cursor, nord.foreground);
+    assert_eq!(cursor.on_foreground_level, None);
+    // nord repeats its accents as bright colors
+    assert!(report
+        .duplicates
+        .iter()
+        .any(|d| d.slots == ("color1".to_string(), "color9".to_string())));
+
+    let red = Color::from_hex("#ff0000");
+    let simulated = Deficiency::Protanopia.simulate(&red);
+    assert!(colorspace::delta_e(&red, &simulated) > 0.1);
+}
@@ -0,0 +1,162 @@
+use colordata::traits::*;
+use colordata::Color;
+
+use crate::structures::Colors;
+
+/// the red, green and blue channels of a color
+pub fn rgb(color: &Color) -> (u8, u8, u8) {
+    let hex = color.hex_stripped();
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2).unwrap_or("00"), 16).unwrap_or(0);
+    (channel(0), channel(2), channel(4))
+}
+
+/// a color from its channels, keeping the alpha of `alpha_from`
+pub fn from_rgb(r: u8, g: u8, b: u8, alpha_from: &Color) -> Color {
+    let alpha = (alpha_from.alpha_f32().clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::from_hex8(&format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, alpha))
+}
+
+/// sRGB channel (0-255) to linear light (0-1)
+pub fn to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// linear light (0-1) to an sRGB channel (0-255)
+pub fn from_linear(value: f64) -> u8 {
+    let c = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// the relative luminance as defined by WCAG 2
+pub fn relative_luminance(color: &Color) -> f64 {
+    let (r, g, b) = rgb(color);
+    0.2126 * to_linear(r) + 0.7152 * to_linear(g) + 0.0722 * to_linear(b)
+}
+
+/// a palette is dark when its background is closer to black than to white
+/// 0.179 is where black and white text have the same contrast
+pub fn is_dark(colors: &Colors) -> bool {
+    relative_luminance(&colors.background) < 0.179
+}
+
+/// the WCAG 2 contrast ratio between two colors, from 1 to 21
+pub fn contrast_ratio(a: &Color, b: &Color) -> f64 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (lighter, darker) = if la > lb { (la, lb) } else { (lb, la) };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// linear sRGB to OKLab
+pub fn linear_to_oklab(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    (
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    )
+}
+
+/// OKLab to linear sRGB, possibly out of the 0-1 range
+pub fn oklab_to_linear(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    (
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+    )
+}
+
+/// a color in OKLab: lightness 0-1, a and b roughly -0.4 to 0.4
+pub fn to_oklab(color: &Color) -> (f64, f64, f64) {
+    let (r, g, b) = rgb(color);
+    linear_to_oklab(to_linear(r), to_linear(g), to_linear(b))
+}
+
+/// a color from OKLab, clipped to sRGB, keeping the alpha of `alpha_from`
+pub fn from_oklab(l: f64, a: f64, b: f64, alpha_from: &Color) -> Color {
+    let (r, g, b) = oklab_to_linear(l, a, b);
+    from_rgb(from_linear(r), from_linear(g), from_linear(b), alpha_from)
+}
+
+/// a color in OKLCH: lightness 0-1, chroma 0-0.4, hue in degrees
+pub fn to_oklch(color: &Color) -> (f64, f64, f64) {
+    let (l, a, b) = to_oklab(color);
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+    (l, (a * a + b * b).sqrt(), hue)
+}
+
+/// a color from OKLCH, keeping the alpha of `alpha_from`
+/// chroma is reduced until the color fits in sRGB, so lightness and hue are kept
+pub fn from_oklch(l: f64, c: f64, h: f64, alpha_from: &Color) -> Color {
+    let l = l.clamp(0.0, 1.0);
+    let (sin, cos) = h.to_radians().sin_cos();
+    let in_gamut = |c: f64| {
+        let (r, g, b) = oklab_to_linear(l, c * cos, c * sin);
+        [r, g, b].iter().all(|v| (-0.0001..=1.0001).contains(v))
+    };
+    let mut chroma = c.max(0.0);
+    if !in_gamut(chroma) {
+        let (mut low, mut high) = (0.0, chroma);
+        for _ in 0..24 {
+            let mid = (low + high) / 2.0;
+            if in_gamut(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            };
+        }
+        chroma = low;
+    };
+    from_oklab(l, chroma * cos, chroma * sin, alpha_from)
+}
+
+/// the perceptual distance between two colors (ΔE in OKLab, 0-1)
+pub fn delta_e(a: &Color, b: &Color) -> f64 {
+    let (l1, a1, b1) = to_oklab(a);
+    let (l2, a2, b2) = to_oklab(b);
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+#[test]
+fn luminance_test() {
+    let black = Color::from_hex("#000000");
+    let white = Color::from_hex("#ffffff");
+    assert_eq!(rgb(&white), (255, 255, 255));
+    assert!(relative_luminance(&black) < 0.001);
+    assert!((relative_luminance(&white) - 1.0).abs() < 0.001);
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let latte =
+        crate::builtin::load_builtin("catppuccin-latte", crate::derive::Strategy::default())
+            .unwrap()
+            .colors
+            .unwrap();
+    assert!(is_dark(&nord));
+    assert!(!is_dark(&latte));
+}
+
+#[test]
+fn oklch_test() {
+    let color = Color::from_hex("#5e81ac");
+    let (l, c, h) = to_oklch(&color);
+    assert_eq!(from_oklch(l, c, h, &color).hex(), color.hex());
+    let white = Color::from_hex("#ffffff");
+    let black = Color::from_hex("#000000");
+    assert!((contrast_ratio(&white, &black) - 21.0).abs() < 0.01);
+    assert!(delta_e(&white, &black) > 0.99);
+}
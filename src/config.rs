@@ -1,18 +1,42 @@
+use std::collections::BTreeMap;
+
 use colordata::traits::*;
 use colordata::Color;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::adjust::Adjust;
+use crate::contrast;
+use crate::contrast::Contrast;
+use crate::derive;
+use crate::extended;
+use crate::icons::Icons;
+use crate::light;
+use crate::reorder;
+use crate::schedule::Schedule;
 use crate::structures::Colors;
 use crate::structures::Wallpaper;
 use crate::utils;
 
-#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub input: Option<String>,
     pub output_directory: Option<String>,
     pub colors: Option<Colors>,
+    pub extended: Option<BTreeMap<u8, Color>>,
     pub wallpaper: Option<Wallpaper>,
+    pub variables: Option<BTreeMap<String, String>>,
+    pub light: Option<bool>,
+    pub reorder: Option<bool>,
+    pub schedule: Option<Schedule>,
+    pub contrast: Option<Contrast>,
+    pub icons: Option<Icons>,
+    pub adjust: Option<Adjust>,
+    /// the strategy for the missing colors of the inputs, the `derive` key
+    pub derive: Option<derive::Strategy>,
+    /// the colors of this document that were derived, for `--verbose`
+    #[serde(skip)]
+    pub derived: Vec<derive::Derived>,
 }
 
 impl Default for Config {
@@ -26,13 +50,24 @@ impl Default for Config {
                 .to_str()
                 .map(str::to_string),
             colors: None,
+            extended: None,
             wallpaper: None,
+            variables: None,
+            light: None,
+            reorder: None,
+            schedule: None,
+            contrast: None,
+            icons: None,
+            adjust: None,
+            derive: None,
+            derived: Vec::new(),
         }
     }
 }
 
 impl Config {
-    pub fn from_toml_str(s: &str) -> Self {
+    /// missing colors are derived with `strategy` unless the document sets `derive`
+    pub fn from_toml_str(s: &str, strategy: derive::Strategy) -> Self {
         let conf: toml::Value = match toml::from_str(s) {
             Ok(some_result) => some_result,
             Err(e) => panic!("an error occured while parsing the TOML: {}", e),
@@ -60,39 +95,89 @@ impl Config {
                     .map(|k| k.as_str().unwrap().to_string())
                     .collect::<Vec<String>>()
             }),
+            backend: f.get("backend").map(|f| f.as_str().unwrap().to_string()),
+            selection: f.get("selection").map(|f| f.as_str().unwrap().to_string()),
+            extensions: f.get("extensions").map(|x| {
+                x.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|k| k.as_str().unwrap().to_string())
+                    .collect::<Vec<String>>()
+            }),
+            recursive: f.get("recursive").and_then(|f| f.as_bool()),
+            exclude_current: f.get("exclude_current").and_then(|f| f.as_bool()),
+            recolor: f.get("recolor").map(|f| f.as_str().unwrap().to_string()),
         });
-        let colors = conf.get("colors").map(|f| Colors {
-            background: Color::from(f.get("background").unwrap().as_str().unwrap()),
-            foreground: Color::from(f.get("foreground").unwrap().as_str().unwrap()),
-            cursor: Color::from(f.get("cursor").unwrap().as_str().unwrap()),
-            colors: [
-                Color::from(f.get("color0").unwrap().as_str().unwrap()),
-                Color::from(f.get("color1").unwrap().as_str().unwrap()),
-                Color::from(f.get("color2").unwrap().as_str().unwrap()),
-                Color::from(f.get("color3").unwrap().as_str().unwrap()),
-                Color::from(f.get("color4").unwrap().as_str().unwrap()),
-                Color::from(f.get("color5").unwrap().as_str().unwrap()),
-                Color::from(f.get("color6").unwrap().as_str().unwrap()),
-                Color::from(f.get("color7").unwrap().as_str().unwrap()),
-                Color::from(f.get("color8").unwrap().as_str().unwrap()),
-                Color::from(f.get("color9").unwrap().as_str().unwrap()),
-                Color::from(f.get("color10").unwrap().as_str().unwrap()),
-                Color::from(f.get("color11").unwrap().as_str().unwrap()),
-                Color::from(f.get("color12").unwrap().as_str().unwrap()),
-                Color::from(f.get("color13").unwrap().as_str().unwrap()),
-                Color::from(f.get("color14").unwrap().as_str().unwrap()),
-                Color::from(f.get("color15").unwrap().as_str().unwrap()),
-            ],
+        let derive =
+            conf.get("derive").and_then(|f| f.as_str()).map(
+                |name| match derive::Strategy::from_name(name) {
+                    Some(strategy) => strategy,
+                    None => panic!("unknown derive strategy: {}", name),
+                },
+            );
+        let mut derived = Vec::new();
+        let colors = conf.get("colors").map(|f| {
+            let color = |name: &str| f.get(name).map(|c| Color::from(c.as_str().unwrap()));
+            let mut slots = [None; 16];
+            for (index, slot) in slots.iter_mut().enumerate() {
+                *slot = color(&format!("color{}", index));
+            }
+            let foreground = color("foreground").unwrap();
+            let (colors, report) = derive::complete(slots, derive.unwrap_or(strategy));
+            derived = report;
+            Colors {
+                background: color("background").unwrap(),
+                foreground,
+                cursor: color("cursor").unwrap_or(foreground),
+                colors,
+            }
+        });
+        let extended = conf
+            .get("colors")
+            .and_then(|f| f.as_table())
+            .map(|t| {
+                t.iter()
+                    .filter_map(|(k, v)| {
+                        let index = k.strip_prefix("color")?.parse::<u8>().ok()?;
+                        match index {
+                            16..=255 => Some((index, Color::from(v.as_str()?))),
+                            _ => None,
+                        }
+                    })
+                    .collect::<BTreeMap<u8, Color>>()
+            })
+            .filter(|m| !m.is_empty());
+        let variables = conf.get("variables").and_then(|f| f.as_table()).map(|t| {
+            t.iter()
+                .map(|(k, v)| {
+                    let value = match v {
+                        toml::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (k.clone(), value)
+                })
+                .collect::<BTreeMap<String, String>>()
         });
         Config {
             input,
             output_directory,
             colors,
+            extended,
             wallpaper,
+            variables,
+            light: conf.get("light").and_then(|f| f.as_bool()),
+            reorder: conf.get("reorder").and_then(|f| f.as_bool()),
+            schedule: conf.get("schedule").map(Schedule::from_toml),
+            contrast: conf.get("contrast").map(Contrast::from_toml),
+            icons: conf.get("icons").map(Icons::from_toml),
+            adjust: conf.get("adjust").map(Adjust::from_toml),
+            derive,
+            derived,
         }
     }
 
-    pub fn from_xresource_str(s: &str) -> Self {
+    /// missing colors are derived with `strategy`
+    pub fn from_xresource_str(s: &str, strategy: derive::Strategy) -> Self {
         let background_regex =
             regex::Regex::new(r#".*background:\s*?(#[a-fA-F0-9]{6,8})\s?"#).unwrap();
         let foreground_regex =
@@ -100,12 +185,13 @@ impl Config {
         let cursor_regex =
             regex::Regex::new(r#".*cursorColor:\s*?(#[a-fA-F0-9]{6,8})\s?"#).unwrap();
         let colors_regex =
-            regex::Regex::new(r#".*(color([0-9]{1,2})):\s*?(#[a-fA-F0-9]{6,8})\s?"#).unwrap();
+            regex::Regex::new(r#".*(color([0-9]{1,3})):\s*?(#[a-fA-F0-9]{6,8})\s?"#).unwrap();
 
         let mut background_color = Color::default();
         let mut foreground_color = Color::default();
-        let mut cursor_color = Color::default();
-        let mut colors_all = [Color::default(); 16];
+        let mut cursor_color = None;
+        let mut colors_all = [None; 16];
+        let mut extended = BTreeMap::new();
         for line in s.split('\n') {
             if let Some(capt) = background_regex.captures(line) {
                 match &capt[1].len() {
@@ -134,52 +220,157 @@ impl Config {
             if let Some(capt) = cursor_regex.captures(line) {
                 match &capt[1].len() {
                     7 | 4 => {
-                        cursor_color = Color::from_hex(&capt[1]);
+                        cursor_color = Some(Color::from_hex(&capt[1]));
                     }
                     9 | 5 => {
-                        cursor_color = Color::from_hex8(&capt[1]);
+                        cursor_color = Some(Color::from_hex8(&capt[1]));
                     }
                     _ => panic!("Invalid hex in: {}", line),
                 }
                 continue;
             };
             if let Some(capt) = colors_regex.captures(line) {
-                match &capt[3].len() {
-                    7 | 4 => {
-                        let index = (&capt[2]).parse::<usize>().unwrap();
-                        if index > 15 {
-                            eprintln!("Ignoring color: {}", &capt[3]);
-                            continue;
-                        };
-                        colors_all[index] = Color::from_hex(&capt[3]);
-                    }
-                    9 | 5 => {
-                        let index = (&capt[2]).parse::<usize>().unwrap();
-                        if index > 15 {
-                            eprintln!("Ignoring color: {}", &capt[3]);
-                            continue;
-                        };
-                        colors_all[index] = Color::from_hex(&capt[3]);
-                    }
+                let color = match &capt[3].len() {
+                    7 | 4 => Color::from_hex(&capt[3]),
+                    9 | 5 => Color::from_hex8(&capt[3]),
                     _ => panic!("Invalid hex in: {}", line),
-                }
+                };
+                let index = (&capt[2]).parse::<usize>().unwrap();
+                match index {
+                    0..=15 => colors_all[index] = Some(color),
+                    16..=255 => {
+                        extended.insert(index as u8, color);
+                    }
+                    _ => eprintln!("Ignoring color: {}", &capt[3]),
+                };
                 continue;
             };
         }
+        let (colors, derived) = derive::complete(colors_all, strategy);
         let colors = Colors {
             background: background_color,
             foreground: foreground_color,
-            cursor: cursor_color,
-            colors: colors_all,
+            cursor: cursor_color.unwrap_or(foreground_color),
+            colors,
         };
         Config {
             input: None,
             output_directory: None,
             wallpaper: None,
             colors: Some(colors),
+            extended: Some(extended).filter(|m| !m.is_empty()),
+            variables: None,
+            light: None,
+            reorder: None,
+            schedule: None,
+            contrast: None,
+            icons: None,
+            adjust: None,
+            derive: None,
+            derived,
+        }
+    }
+
+    /// read a config, Xresources or toml document, deriving missing colors with `strategy`
+    pub fn from_document(s: &str, strategy: derive::Strategy) -> Self {
+        let document_type = utils::detect_string_type(s);
+        match document_type {
+            0 => Config::from_toml_str(s, strategy),
+            1 => Config::from_xresource_str(s, strategy),
+            _ => panic!("Unrecognized document format"),
+        }
+    }
+
+    /// read a config, Xresources or toml document from a file
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P, strategy: derive::Strategy) -> Self {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(s) => Config::from_document(&s, strategy),
+            Err(e) => panic!("could not read {}: {}", path.as_ref().display(), e),
         }
     }
 
+    /// the colors templates are rendered with: the light variant when `light` is set,
+    /// with the `[adjust]` steps, after the contrast pass when `[contrast]` is enabled
+    pub fn palette(&self) -> Option<Colors> {
+        let colors = self.base_palette()?;
+        match &self.contrast {
+            Some(settings) if settings.enabled() => Some(contrast::enforce(&colors, settings).0),
+            _ => Some(colors),
+        }
+    }
+
+    /// what the contrast pass changes in the palette
+    pub fn contrast_adjustments(&self) -> Vec<contrast::Adjustment> {
+        match (&self.contrast, self.base_palette()) {
+            (Some(settings), Some(colors)) if settings.enabled() => {
+                contrast::enforce(&colors, settings).1
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn base_palette(&self) -> Option<Colors> {
+        let colors = match self.light {
+            Some(true) => self.ordered_colors().map(|c| light::light_variant(&c)),
+            _ => self.ordered_colors(),
+        }?;
+        Some(self.adjusted(&colors))
+    }
+
+    /// the colors with the `[adjust]` steps applied
+    fn adjusted(&self, colors: &Colors) -> Colors {
+        match &self.adjust {
+            Some(adjust) => adjust.apply(colors),
+            None => *colors,
+        }
+    }
+
+    /// the colors as read, with the accents in their ansi slots when `reorder` is set
+    fn ordered_colors(&self) -> Option<Colors> {
+        match self.reorder {
+            Some(true) => self.colors.map(|c| reorder::reorder(&c)),
+            _ => self.colors,
+        }
+    }
+
+    /// color16 .. color255: the ones read from the input,
+    /// and a cube and gray ramp generated from the palette for the others
+    pub fn extended_palette(&self) -> Option<Vec<Color>> {
+        self.palette()
+            .map(|colors| extended::complete(&colors, self.extended.as_ref()))
+    }
+
+    /// the variables available to templates: the colors from `to_json`,
+    /// `color16` .. `color255` from the extended palette,
+    /// `dark_*` with the colors as read and `light_*` with their light variant,
+    /// and the `[variables]` overrides on top
+    pub fn template_data(&self) -> serde_json::Map<String, serde_json::Value> {
+        let rendered = Config {
+            colors: self.palette(),
+            ..self.clone()
+        };
+        let mut data: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&rendered.to_json()).unwrap();
+        if let Some(extended) = self.extended_palette() {
+            for (index, color) in extended.iter().enumerate() {
+                insert_color(&mut data, &format!("color{}", index + 16), color);
+            }
+        };
+        if let Some(colors) = &self.ordered_colors() {
+            data.extend(color_variables("dark_", &self.adjusted(colors)));
+            data.extend(color_variables(
+                "light_",
+                &self.adjusted(&light::light_variant(colors)),
+            ));
+        };
+        if let Some(variables) = &self.variables {
+            for (key, value) in variables {
+                data.insert(key.clone(), serde_json::Value::String(value.clone()));
+            }
+        };
+        data
+    }
+
     pub fn to_json(&self) -> String {
         let default = Color::default();
         format!(
@@ -1158,14 +1349,55 @@ impl Config {
     }
 }
 
+/// the same variables `to_json` has for every color, with `prefix` in front of the names
+pub fn color_variables(
+    prefix: &str,
+    colors: &Colors,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut slots = vec![
+        ("background".to_string(), colors.background),
+        ("foreground".to_string(), colors.foreground),
+        ("cursor".to_string(), colors.cursor),
+    ];
+    for (index, color) in colors.colors.iter().enumerate() {
+        slots.push((format!("color{}", index), *color));
+    }
+    let mut variables = serde_json::Map::new();
+    for (name, color) in slots {
+        insert_color(&mut variables, &format!("{}{}", prefix, name), &color);
+    }
+    variables
+}
+
+/// `name`, `name_rgb`, `name_hex8` .. for one color
+fn insert_color(
+    variables: &mut serde_json::Map<String, serde_json::Value>,
+    name: &str,
+    color: &Color,
+) {
+    let values = [
+        ("", color.hex()),
+        ("_rgb", color.rgb()),
+        ("_rgba", color.rgba()),
+        ("_hex8", color.hex8()),
+        ("_xrgba", color.xrgba()),
+        ("_alpha", color.alpha_f32().to_string()),
+        ("_hex_stripped", color.hex_stripped()),
+        ("_hex8_stripped", color.hex8_stripped()),
+        ("_rgb_stripped", color.rgb_stripped()),
+        ("_rgba_stripped", color.rgba_stripped()),
+    ];
+    for (suffix, value) in values.iter() {
+        variables.insert(
+            format!("{}{}", name, suffix),
+            serde_json::Value::String(value.clone()),
+        );
+    }
+}
+
 impl From<&str> for Config {
     fn from(s: &str) -> Self {
-        let document_type = utils::detect_string_type(s);
-        match document_type {
-            0 => Config::from_toml_str(s),
-            1 => Config::from_xresource_str(s),
-            _ => panic!("Unrecognized document format"),
-        }
+        Config::from_document(s, derive::Strategy::default())
     }
 }
 
@@ -1293,3 +1525,49 @@ fn json_test() {
     let jason = conf.to_json();
     assert!(serde_json::from_str::<serde_json::Value>(&jason).is_ok());
 }
+
+#[test]
+fn extended_colors_test() {
+    let string = r#"*.foreground:   #cac0a9
+    *.background:   #1c1f2b
+    *.color0:       #242837
+    *.color1:       #f14360
+    *.color2:       #aecc00
+    *.color3:       #ff9d35
+    *.color4:       #75b0ff
+    *.color5:       #c651e5
+    *.color6:       #4ce7ff
+    *.color7:       #fbe1a3
+    *.color17:      #123456
+    *.color300:     #654321
+    "#;
+    let conf = Config::from(string);
+    let extended = conf.extended.clone().unwrap();
+    assert_eq!(extended.keys().copied().collect::<Vec<u8>>(), vec![17]);
+
+    let data = conf.template_data();
+    assert_eq!(data["color17"], "#123456");
+    assert!(data.contains_key("color255_rgb"));
+    assert!(!data.contains_key("color256"));
+}
+
+#[test]
+fn partial_colors_test() {
+    let string = r#"*.foreground:   #cac0a9
+    *.background:   #1c1f2b
+    *.cursorColor:  #ffffff
+    "#;
+    let conf = Config::from_xresource_str(string, derive::Strategy::Copy);
+    let colors = conf.colors.unwrap();
+    assert_eq!(colors.colors, [Color::default(); 16]);
+    assert_eq!(colors.cursor, Color::from_hex("#ffffff"));
+    assert!(conf.derived.is_empty());
+
+    let conf = Config::from_toml_str(
+        "derive = \"copy\"\n[colors]\nbackground = \"#1c1f2b\"\nforeground = \"#cac0a9\"\ncolor1 = \"#f14360\"",
+        derive::Strategy::Lighten,
+    );
+    assert_eq!(conf.derive, Some(derive::Strategy::Copy));
+    assert_eq!(conf.colors.unwrap().colors[9], Color::from_hex("#f14360"));
+    assert_eq!(conf.derived, vec![derive::Derived { slot: 9, from: 1 }]);
+}
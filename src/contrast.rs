@@ -0,0 +1,217 @@
+use colordata::traits::*;
+use colordata::Color;
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+const DEFAULT_MIN_RATIO: f64 = 4.5;
+const DEFAULT_MIN_RATIO_COLORS: f64 = 3.0;
+
+/// the `[contrast]` section: raise the contrast of the colors against the background
+/// `min_ratio` is the WCAG 2 ratio for foreground and cursor, `min_ratio_colors` for the
+/// ansi colors, and `min_apca` an optional APCA lightness contrast (Lc) for all of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Contrast {
+    pub enable: Option<bool>,
+    pub min_ratio: Option<f64>,
+    pub min_ratio_colors: Option<f64>,
+    pub min_apca: Option<f64>,
+}
+
+/// a color that was changed by the contrast pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub slot: String,
+    pub before: Color,
+    pub after: Color,
+    pub reason: String,
+}
+
+impl Contrast {
+    pub fn from_toml(value: &toml::Value) -> Self {
+        let number = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+        };
+        Contrast {
+            enable: value.get("enable").and_then(|v| v.as_bool()),
+            min_ratio: number("min_ratio"),
+            min_ratio_colors: number("min_ratio_colors"),
+            min_apca: number("min_apca"),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enable.unwrap_or(true)
+    }
+}
+
+/// the APCA lightness contrast (Lc) of `text` on `background`, roughly -108 to 106
+/// positive for dark text on a light background, negative the other way around
+/// see https://github.com/Myndex/apca-w3 (0.0.98G-4g)
+pub fn apca_contrast(text: &Color, background: &Color) -> f64 {
+    let luminance = |color: &Color| {
+        let (r, g, b) = colorspace::rgb(color);
+        let channel = |c: u8| (c as f64 / 255.0).powf(2.4);
+        let y = 0.2126729 * channel(r) + 0.7151522 * channel(g) + 0.0721750 * channel(b);
+        // soft clamp near black
+        if y < 0.022 {
+            y + (0.022 - y).powf(1.414)
+        } else {
+            y
+        }
+    };
+    let (text, background) = (luminance(text), luminance(background));
+    if (background - text).abs() < 0.0005 {
+        return 0.0;
+    };
+    let contrast = if background > text {
+        let sapc = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+        if sapc < 0.1 {
+            0.0
+        } else {
+            sapc - 0.027
+        }
+    } else {
+        let sapc = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+        if sapc > -0.1 {
+            0.0
+        } else {
+            sapc + 0.027
+        }
+    };
+    contrast * 100.0
+}
+
+/// the slots that are read as text on the background, with their minimum WCAG ratio
+/// the black slot of a dark palette and the white slots of a light one are left alone
+fn text_slots(colors: &Colors, settings: &Contrast) -> Vec<(String, f64)> {
+    let min_ratio = settings.min_ratio.unwrap_or(DEFAULT_MIN_RATIO);
+    let min_ratio_colors = settings
+        .min_ratio_colors
+        .unwrap_or(DEFAULT_MIN_RATIO_COLORS);
+    let dark = colorspace::is_dark(colors);
+    let mut slots = vec![
+        ("foreground".to_string(), min_ratio),
+        ("cursor".to_string(), min_ratio),
+    ];
+    for index in 0..16 {
+        let background_like = if dark {
+            index == 0
+        } else {
+            index == 7 || index == 15
+        };
+        if !background_like {
+            slots.push((format!("color{}", index), min_ratio_colors));
+        };
+    }
+    slots
+}
+
+/// why `color` is not readable on `background`, or None when it is
+fn shortfall(
+    color: &Color,
+    background: &Color,
+    min_ratio: f64,
+    min_apca: Option<f64>,
+) -> Option<String> {
+    let ratio = colorspace::contrast_ratio(color, background);
+    if ratio < min_ratio {
+        return Some(format!("WCAG {:.2} < {:.1}", ratio, min_ratio));
+    };
+    if let Some(min_apca) = min_apca {
+        let lc = apca_contrast(color, background).abs();
+        if lc < min_apca {
+            return Some(format!("APCA Lc {:.0} < {:.0}", lc, min_apca));
+        };
+    };
+    None
+}
+
+/// move the OKLab lightness of every text slot away from the background
+/// until it meets the minimum contrast; hue and chroma are kept where sRGB allows
+/// returns the new colors and what was changed
+pub fn enforce(colors: &Colors, settings: &Contrast) -> (Colors, Vec<Adjustment>) {
+    let mut result = *colors;
+    let mut adjustments = Vec::new();
+    let step = if colorspace::is_dark(colors) {
+        0.01
+    } else {
+        -0.01
+    };
+    for (slot, min_ratio) in text_slots(colors, settings) {
+        let before = match result.slot(&slot) {
+            Some(c) => c,
+            None => continue,
+        };
+        let reason = match shortfall(&before, &colors.background, min_ratio, settings.min_apca) {
+            Some(r) => r,
+            None => continue,
+        };
+        let (mut lightness, chroma, hue) = colorspace::to_oklch(&before);
+        let mut after = before;
+        while shortfall(&after, &colors.background, min_ratio, settings.min_apca).is_some()
+            && (0.0..=1.0).contains(&(lightness + step))
+        {
+            lightness += step;
+            after = colorspace::from_oklch(lightness, chroma, hue, &before);
+        }
+        if let Some(color) = result.slot_mut(&slot) {
+            *color = after;
+        };
+        adjustments.push(Adjustment {
+            slot,
+            before,
+            after,
+            reason,
+        });
+    }
+    (result, adjustments)
+}
+
+/// print what the contrast pass changed and why
+pub fn print_report(adjustments: &[Adjustment], background: &Color) {
+    for adjustment in adjustments {
+        println!(
+            "{}",
+            format!(
+                "contrast: {} {} -> {} ({}, now WCAG {:.2}, APCA Lc {:.0})",
+                adjustment.slot,
+                adjustment.before.hex(),
+                adjustment.after.hex(),
+                adjustment.reason,
+                colorspace::contrast_ratio(&adjustment.after, background),
+                apca_contrast(&adjustment.after, background).abs()
+            )
+            .yellow()
+        );
+    }
+}
+
+#[test]
+fn contrast_test() {
+    let black = Color::from_hex("#000000");
+    let white = Color::from_hex("#ffffff");
+    assert!((apca_contrast(&black, &white) - 106.04).abs() < 0.1);
+    assert!((apca_contrast(&white, &black) + 107.88).abs() < 0.1);
+
+    // nord's bright black is famously hard to read
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let settings = Contrast {
+        min_apca: Some(30.0),
+        ..Contrast::default()
+    };
+    let (fixed, adjustments) = enforce(&nord, &settings);
+    assert!(adjustments.iter().any(|a| a.slot == "color8"));
+    assert!(colorspace::contrast_ratio(&fixed.colors[8], &fixed.background) >= 3.0);
+    assert!(colorspace::contrast_ratio(&fixed.foreground, &fixed.background) >= 4.5);
+    assert_eq!(fixed.colors[0], nord.colors[0]);
+    assert!(enforce(&fixed, &settings).1.is_empty());
+}
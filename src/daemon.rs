@@ -0,0 +1,226 @@
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use colordata::traits::*;
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::structures::Colors;
+
+/// how long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// a request sent to the daemon, one JSON object per line
+/// eg. {"command": "set-wallpaper", "path": "/home/user/Pictures/wall.png"}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    SetTheme { theme: String },
+    SetWallpaper { path: String },
+    Reload,
+    GetPalette,
+    ScheduleTick,
+}
+
+/// the answer of the daemon, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<serde_json::Value>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Response {
+            ok: true,
+            ..Response::default()
+        }
+    }
+
+    pub fn error(message: &str) -> Self {
+        Response {
+            ok: false,
+            message: Some(message.to_string()),
+            palette: None,
+        }
+    }
+}
+
+impl Request {
+    /// build a request from command line words, eg. `set-theme ~/.Xresources`
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        match (args.first().map(String::as_str), args.get(1)) {
+            (Some("set-theme"), Some(theme)) => Some(Request::SetTheme {
+                theme: theme.clone(),
+            }),
+            (Some("set-wallpaper"), Some(path)) => {
+                Some(Request::SetWallpaper { path: path.clone() })
+            }
+            (Some("reload"), None) => Some(Request::Reload),
+            (Some("get-palette"), None) => Some(Request::GetPalette),
+            (Some("schedule-tick"), None) => Some(Request::ScheduleTick),
+            _ => None,
+        }
+    }
+}
+
+/// the palette as answered to get-palette, colors in hex
+pub fn palette(colors: &Colors) -> serde_json::Value {
+    serde_json::json!({
+        "background": colors.background.hex(),
+        "foreground": colors.foreground.hex(),
+        "cursor": colors.cursor.hex(),
+        "colors": colors.colors.iter().map(|c| c.hex()).collect::<Vec<String>>(),
+    })
+}
+
+/// $XDG_RUNTIME_DIR/colstract.sock, or in the user's cache directory when it is not set
+/// never in the shared /tmp, where other users could connect
+pub fn socket_path() -> PathBuf {
+    let runtime_dir = match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(val) => PathBuf::from(val),
+        Err(_) => {
+            let cache = match std::env::var("XDG_CACHE_HOME") {
+                Ok(val) => PathBuf::from(val),
+                Err(_) => PathBuf::from(std::env::var("HOME").unwrap()).join(".cache"),
+            };
+            let dir = cache.join("colstract");
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: $XDG_RUNTIME_DIR is not set, using {}",
+                    dir.display()
+                )
+                .yellow()
+            );
+            dir
+        }
+    };
+    runtime_dir.join("colstract.sock")
+}
+
+/// whether a daemon answers on the socket
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+/// listen on the socket and answer every request with `handler`
+pub fn serve<F: FnMut(Request) -> Response>(mut handler: F) {
+    let path = socket_path();
+    if path.exists() {
+        if is_running() {
+            eprintln!("{}", "A colstract daemon is already running".red());
+            std::process::exit(1);
+        };
+        // left behind by a daemon that did not exit cleanly
+        let _ = std::fs::remove_file(&path);
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    };
+    let listener = match UnixListener::bind(&path) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not listen on {}: {}", path.display(), e).red()
+            );
+            std::process::exit(1);
+        }
+    };
+    // only the owner may connect
+    if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+        eprintln!(
+            "{}",
+            format!("Could not restrict {}: {}", path.display(), e).red()
+        );
+        std::process::exit(1);
+    };
+    println!("{}", format!("listening on {}", path.display()).green());
+
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{}", format!("Connection failed: {}", e).red());
+                continue;
+            }
+        };
+        // a client that never finishes its line must not block the others
+        if let Err(e) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+            eprintln!("{}", format!("Connection failed: {}", e).red());
+            continue;
+        };
+        let mut line = String::new();
+        if let Err(e) = BufReader::new(&stream).read_line(&mut line) {
+            eprintln!("{}", format!("Could not read request: {}", e).red());
+            continue;
+        };
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                // a bad theme must not take the daemon down
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(request))) {
+                    Ok(r) => r,
+                    Err(_) => Response::error("the request could not be completed"),
+                }
+            }
+            Err(e) => Response::error(&format!("invalid request: {}", e)),
+        };
+        let mut answer = serde_json::to_string(&response).unwrap();
+        answer.push('\n');
+        if let Err(e) = stream.write_all(answer.as_bytes()) {
+            eprintln!("{}", format!("Could not send response: {}", e).red());
+        };
+    }
+}
+
+/// send `request` to the daemon every `interval` from a background thread
+pub fn tick_every(interval: std::time::Duration, request: Request) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        if let Err(e) = send(&request) {
+            eprintln!("{}", format!("Could not reach the daemon: {}", e).yellow());
+        };
+    });
+}
+
+/// send one request to a running daemon and wait for the response
+pub fn send(request: &Request) -> std::io::Result<Response> {
+    let mut stream = UnixStream::connect(socket_path())?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut answer = String::new();
+    BufReader::new(&stream).read_line(&mut answer)?;
+    Ok(serde_json::from_str(&answer)?)
+}
+
+#[test]
+fn protocol_test() {
+    let request = Request::SetWallpaper {
+        path: "/home/user/Pictures/wall.png".to_string(),
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(
+        json,
+        r#"{"command":"set-wallpaper","path":"/home/user/Pictures/wall.png"}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<Request>(r#"{"command":"reload"}"#).unwrap(),
+        Request::Reload
+    );
+    assert_eq!(
+        Request::from_args(&["get-palette".to_string()]),
+        Some(Request::GetPalette)
+    );
+}
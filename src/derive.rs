@@ -0,0 +1,162 @@
+use colordata::Color;
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::utils;
+
+/// how a missing bright color (color8 .. color15) is made from its base color, and the other way around
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// raise the OKLCH lightness, keeping the chroma, the default
+    #[default]
+    Lighten,
+    /// raise the lightness a little and the chroma more
+    Saturate,
+    /// use the same color
+    Copy,
+}
+
+impl Strategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lighten" => Some(Strategy::Lighten),
+            "saturate" => Some(Strategy::Saturate),
+            "copy" => Some(Strategy::Copy),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::Lighten => "lighten",
+            Strategy::Saturate => "saturate",
+            Strategy::Copy => "copy",
+        }
+    }
+
+    /// lightness and chroma factor from a base color to its bright color
+    /// black to bright black takes the largest step, white to bright white a smaller one
+    fn step(&self, index: usize) -> (f64, f64) {
+        let lightness = match index {
+            0 => 0.2,
+            7 => 0.1,
+            _ => 0.08,
+        };
+        match self {
+            Strategy::Lighten => (lightness, 1.0),
+            Strategy::Saturate => (lightness / 2.0, 1.25),
+            Strategy::Copy => (0.0, 1.0),
+        }
+    }
+}
+
+/// a slot that was missing and the slot it was made from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derived {
+    pub slot: usize,
+    pub from: usize,
+}
+
+/// fill the missing colors from their base or bright counterpart
+/// when both color{n} and color{n+8} are missing they stay at the default color
+pub fn complete(slots: [Option<Color>; 16], strategy: Strategy) -> ([Color; 16], Vec<Derived>) {
+    let mut colors = [Color::default(); 16];
+    let mut derived = Vec::new();
+    for index in 0..8 {
+        let (base, bright) = (slots[index], slots[index + 8]);
+        let (lightness, chroma) = strategy.step(index);
+        let shift = |color: &Color, sign: f64| {
+            let (l, c, h) = colorspace::to_oklch(color);
+            colorspace::from_oklch(
+                (l + lightness * sign).clamp(0.0, 1.0),
+                c * chroma.powf(sign),
+                h,
+                color,
+            )
+        };
+        match (base, bright) {
+            (Some(base), Some(bright)) => {
+                colors[index] = base;
+                colors[index + 8] = bright;
+            }
+            (Some(base), None) => {
+                colors[index] = base;
+                colors[index + 8] = shift(&base, 1.0);
+                derived.push(Derived {
+                    slot: index + 8,
+                    from: index,
+                });
+            }
+            (None, Some(bright)) => {
+                colors[index] = shift(&bright, -1.0);
+                colors[index + 8] = bright;
+                derived.push(Derived {
+                    slot: index,
+                    from: index + 8,
+                });
+            }
+            (None, None) => {}
+        };
+    }
+    (colors, derived)
+}
+
+/// list the derived colors with `--verbose`
+pub fn print_report(derived: &[Derived], strategy: Strategy) {
+    if !utils::verbose() {
+        return;
+    };
+    for d in derived {
+        println!(
+            "{}",
+            format!(
+                "derived: color{} from color{} ({})",
+                d.slot,
+                d.from,
+                strategy.as_str()
+            )
+            .yellow()
+        );
+    }
+}
+
+#[test]
+fn derive_test() {
+    let mut slots = [None; 16];
+    for (index, hex) in ["#000000", "#bf616a", "#a3be8c", "#ebcb8b"]
+        .iter()
+        .enumerate()
+    {
+        slots[index] = Some(Color::from_hex(hex));
+    }
+    for (index, hex) in ["#81a1c1", "#b48ead", "#88c0d0", "#e5e9f0"]
+        .iter()
+        .enumerate()
+    {
+        slots[index + 12] = Some(Color::from_hex(hex));
+    }
+    let (colors, derived) = complete([None; 16], Strategy::Lighten);
+    assert_eq!(colors, [Color::default(); 16]);
+    assert!(derived.is_empty());
+
+    let (colors, derived) = complete(slots, Strategy::Lighten);
+    assert_eq!(derived.len(), 8);
+    assert_eq!(derived[1], Derived { slot: 9, from: 1 });
+    assert_eq!(derived[4], Derived { slot: 4, from: 12 });
+    // bright colors are lighter, and keep the hue
+    for index in 0..8 {
+        let (base_l, _, base_h) = colorspace::to_oklch(&colors[index]);
+        let (bright_l, _, bright_h) = colorspace::to_oklch(&colors[index + 8]);
+        assert!(bright_l > base_l);
+        if index != 0 && index != 7 {
+            assert!((base_h - bright_h).abs() < 2.0);
+        };
+    }
+
+    let (colors, _) = complete(slots, Strategy::Copy);
+    assert_eq!(colors[9], colors[1]);
+    assert_eq!(colors[4], colors[12]);
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use colordata::Color;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// the colors after the base 16 in a 256 color palette: color16 .. color255
+pub const EXTENDED_COLORS: usize = 240;
+
+fn mix(from: (f64, f64, f64), to: (f64, f64, f64), t: f64) -> (f64, f64, f64) {
+    (
+        from.0 + (to.0 - from.0) * t,
+        from.1 + (to.1 - from.1) * t,
+        from.2 + (to.2 - from.2) * t,
+    )
+}
+
+/// the 6x6x6 cube (color16 .. color231) and the 24 grays (color232 .. color255) built from the theme
+/// the corners of the cube are the background, the six accents and the foreground where xterm has
+/// black, red .. cyan and white, and the grays run from the darker of the two to the lighter, all in OKLab
+pub fn generate(colors: &Colors) -> Vec<Color> {
+    let lab = colorspace::to_oklab;
+    // indexed by red + 2 * green + 4 * blue
+    let corners = [
+        lab(&colors.background),
+        lab(&colors.colors[1]),
+        lab(&colors.colors[2]),
+        lab(&colors.colors[3]),
+        lab(&colors.colors[4]),
+        lab(&colors.colors[5]),
+        lab(&colors.colors[6]),
+        lab(&colors.foreground),
+    ];
+    let color = |(l, a, b): (f64, f64, f64)| colorspace::from_oklab(l, a, b, &colors.foreground);
+    let mut extended = Vec::with_capacity(EXTENDED_COLORS);
+    for red in 0..6 {
+        for green in 0..6 {
+            for blue in 0..6 {
+                let (r, g, b) = (red as f64 / 5.0, green as f64 / 5.0, blue as f64 / 5.0);
+                // the square at blue 0 or 1, then between the two
+                let face = |offset: usize| {
+                    mix(
+                        mix(corners[offset], corners[offset + 1], r),
+                        mix(corners[offset + 2], corners[offset + 3], r),
+                        g,
+                    )
+                };
+                extended.push(color(mix(face(0), face(4), b)));
+            }
+        }
+    }
+    // xterm's grays get lighter, also when the background is the light end
+    let (dark, light) = match corners[0].0 <= corners[7].0 {
+        true => (corners[0], corners[7]),
+        false => (corners[7], corners[0]),
+    };
+    for step in 0..24 {
+        let t = (step + 1) as f64 / 25.0;
+        extended.push(color(mix(dark, light, t)));
+    }
+    extended
+}
+
+/// color16 .. color255: the ones read from the input, the generated ones for the rest
+pub fn complete(colors: &Colors, parsed: Option<&BTreeMap<u8, Color>>) -> Vec<Color> {
+    let mut extended = generate(colors);
+    for (&index, color) in parsed.into_iter().flatten() {
+        if let Some(slot) = (index as usize)
+            .checked_sub(16)
+            .and_then(|i| extended.get_mut(i))
+        {
+            *slot = *color;
+        };
+    }
+    extended
+}
+
+#[test]
+fn extended_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let extended = generate(&nord);
+    assert_eq!(extended.len(), EXTENDED_COLORS);
+    let close = |a: &Color, b: &Color| colorspace::delta_e(a, b) < 0.01;
+    // the corners of the cube: 16 black, 196 red, 21 blue, 231 white
+    assert!(close(&extended[0], &nord.background));
+    assert!(close(&extended[196 - 16], &nord.colors[1]));
+    assert!(close(&extended[21 - 16], &nord.colors[4]));
+    assert!(close(&extended[231 - 16], &nord.foreground));
+    // the grays get lighter
+    let lightness = |color: &Color| colorspace::to_oklab(color).0;
+    for pair in extended[216..].windows(2) {
+        assert!(lightness(&pair[0]) < lightness(&pair[1]));
+    }
+    // and so they do in a light theme
+    let light = Colors {
+        background: nord.foreground,
+        foreground: nord.background,
+        ..nord
+    };
+    for pair in generate(&light)[216..].windows(2) {
+        assert!(lightness(&pair[0]) < lightness(&pair[1]));
+    }
+
+    let mut parsed = BTreeMap::new();
+    parsed.insert(17, Color::from_hex("#123456"));
+    parsed.insert(3, Color::from_hex("#654321"));
+    let completed = complete(&nord, Some(&parsed));
+    assert_eq!(completed[1], parsed[&17]);
+    assert_eq!(completed[0], extended[0]);
+    assert_eq!(completed[2..], extended[2..]);
+}
@@ -0,0 +1,262 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::Rgba;
+use image::RgbaImage;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::structures::Colors;
+use crate::utils;
+
+const GENERATED_FILE: &str = "generated-wallpaper.png";
+/// gradients are sampled from this many precomputed colors
+const GRADIENT_STEPS: usize = 512;
+/// how far the lightness of noise strays from the color, in OKLab
+const NOISE_AMOUNT: f64 = 0.03;
+/// the largest width or height, 16384x16384 is already 1 GiB of pixels
+const MAX_SIZE: u32 = 16384;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Solid,
+    Linear,
+    Radial,
+    Stripes,
+    Noise,
+}
+
+impl Style {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "solid" => Some(Style::Solid),
+            "linear" => Some(Style::Linear),
+            "radial" => Some(Style::Radial),
+            "stripes" => Some(Style::Stripes),
+            "noise" => Some(Style::Noise),
+            _ => None,
+        }
+    }
+
+    /// the slots used when `--colors` is not given
+    fn default_slots(&self) -> &'static [&'static str] {
+        match self {
+            Style::Solid | Style::Noise => &["background"],
+            Style::Linear | Style::Radial => &["background", "color4"],
+            Style::Stripes => &["background", "color0"],
+        }
+    }
+}
+
+/// `colstract wallpaper generate [--style S] [--size WxH] [--colors slot,slot]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    pub style: Style,
+    pub width: u32,
+    pub height: u32,
+    pub slots: Vec<String>,
+}
+
+impl Generator {
+    /// remove the generate subcommand and its options from the arguments, if there is one
+    pub fn take(args: &mut Vec<String>) -> Option<Self> {
+        if args.get(1).map(String::as_str) != Some("wallpaper")
+            || args.get(2).map(String::as_str) != Some("generate")
+        {
+            return None;
+        };
+        args.drain(1..3);
+        let usage = || -> ! {
+            eprintln!(
+                "{}",
+                "Usage: colstract wallpaper generate [--style solid|linear|radial|stripes|noise] [--size WxH] [--colors slot,slot]"
+                    .red()
+            );
+            std::process::exit(1);
+        };
+        let style = match utils::take_option(args, &["--style"]) {
+            Some(name) => Style::from_name(&name).unwrap_or_else(|| usage()),
+            None => Style::Solid,
+        };
+        let (width, height) = match utils::take_option(args, &["--size"]) {
+            Some(size) => parse_size(&size).unwrap_or_else(|| usage()),
+            None => (1920, 1080),
+        };
+        let slots = match utils::take_option(args, &["--colors"]) {
+            Some(list) => list.split(',').map(|s| s.trim().to_string()).collect(),
+            None => style
+                .default_slots()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        };
+        Some(Generator {
+            style,
+            width,
+            height,
+            slots,
+        })
+    }
+
+    pub fn render(&self, colors: &Colors) -> Result<RgbaImage, String> {
+        let mut stops = Vec::new();
+        for slot in &self.slots {
+            match colors.slot(slot) {
+                Some(color) => stops.push(colorspace::to_oklab(&color)),
+                None => return Err(format!("no color named {}", slot)),
+            };
+        }
+        if stops.is_empty() {
+            return Err("no colors to generate from".to_string());
+        };
+        let (width, height) = (self.width, self.height);
+        let image = match self.style {
+            Style::Solid => {
+                let color = to_rgba(stops[0]);
+                RgbaImage::from_fn(width, height, |_, _| color)
+            }
+            Style::Linear => {
+                let gradient = gradient(&stops);
+                RgbaImage::from_fn(width, height, |_, y| {
+                    sample(&gradient, y as f64 / (height.max(2) - 1) as f64)
+                })
+            }
+            Style::Radial => {
+                let gradient = gradient(&stops);
+                let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+                let radius = (cx * cx + cy * cy).sqrt();
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let (dx, dy) = (x as f64 - cx, y as f64 - cy);
+                    sample(&gradient, (dx * dx + dy * dy).sqrt() / radius)
+                })
+            }
+            Style::Stripes => {
+                let colors = stops.iter().map(|s| to_rgba(*s)).collect::<Vec<Rgba<u8>>>();
+                let stripe = (width.max(height) / 32).max(1);
+                RgbaImage::from_fn(width, height, |x, y| {
+                    colors[((x + y) / stripe) as usize % colors.len()]
+                })
+            }
+            Style::Noise => {
+                // a few shades around every color, picked at random for every pixel
+                let shades = stops
+                    .iter()
+                    .flat_map(|&(l, a, b)| {
+                        (0..16).map(move |i| {
+                            let offset = (i as f64 / 15.0 * 2.0 - 1.0) * NOISE_AMOUNT;
+                            to_rgba((l + offset, a, b))
+                        })
+                    })
+                    .collect::<Vec<Rgba<u8>>>();
+                let mut random = XorShift::seeded();
+                RgbaImage::from_fn(width, height, |_, _| {
+                    shades[(random.next_u64() % shades.len() as u64) as usize]
+                })
+            }
+        };
+        Ok(image)
+    }
+}
+
+/// "1920x1080" to (1920, 1080), None for sizes of 0 or above `MAX_SIZE`
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height) = (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?);
+    let valid = 1..=MAX_SIZE;
+    if !valid.contains(&width) || !valid.contains(&height) {
+        return None;
+    };
+    Some((width, height))
+}
+
+fn to_rgba((l, a, b): (f64, f64, f64)) -> Rgba<u8> {
+    let color = colorspace::from_oklab(l, a, b, &colordata::Color::default());
+    let (r, g, b) = colorspace::rgb(&color);
+    Rgba([r, g, b, 255])
+}
+
+/// evenly spaced stops, interpolated in OKLab
+fn gradient(stops: &[(f64, f64, f64)]) -> Vec<Rgba<u8>> {
+    if stops.len() == 1 {
+        return vec![to_rgba(stops[0])];
+    };
+    (0..GRADIENT_STEPS)
+        .map(|step| {
+            let t = step as f64 / (GRADIENT_STEPS - 1) as f64 * (stops.len() - 1) as f64;
+            let index = (t.floor() as usize).min(stops.len() - 2);
+            let (from, to, f) = (stops[index], stops[index + 1], t - index as f64);
+            to_rgba((
+                from.0 + (to.0 - from.0) * f,
+                from.1 + (to.1 - from.1) * f,
+                from.2 + (to.2 - from.2) * f,
+            ))
+        })
+        .collect()
+}
+
+/// the color at `t` (0-1) along the gradient
+fn sample(gradient: &[Rgba<u8>], t: f64) -> Rgba<u8> {
+    let index = (t.clamp(0.0, 1.0) * (gradient.len() - 1) as f64).round() as usize;
+    gradient[index]
+}
+
+/// xorshift64, seeded like `utils::random_index`
+struct XorShift(u64);
+
+impl XorShift {
+    fn seeded() -> Self {
+        XorShift(utils::random_index(usize::MAX) as u64 | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// write the image as `generated-wallpaper.png` in the output directory
+pub fn save(image: &RgbaImage, output_dir: &Path) -> Result<PathBuf, String> {
+    let path = output_dir.join(GENERATED_FILE);
+    image.save(&path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[test]
+fn generate_test() {
+    let mut args = [
+        "colstract",
+        "wallpaper",
+        "generate",
+        "--style",
+        "linear",
+        "--size",
+        "64x32",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<String>>();
+    let generator = Generator::take(&mut args).unwrap();
+    assert_eq!(args, vec!["colstract".to_string()]);
+    assert_eq!((generator.width, generator.height), (64, 32));
+    assert_eq!(parse_size("16384x1"), Some((16384, 1)));
+    assert_eq!(parse_size("100000x100000"), None);
+    assert_eq!(parse_size("0x1080"), None);
+
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let image = generator.render(&nord).unwrap();
+    let (r, g, b) = colorspace::rgb(&nord.background);
+    assert_eq!(image.get_pixel(10, 0), &Rgba([r, g, b, 255]));
+    let (r, g, b) = colorspace::rgb(&nord.colors[4]);
+    assert_eq!(image.get_pixel(10, 31), &Rgba([r, g, b, 255]));
+
+    let missing = Generator {
+        slots: vec!["color99".to_string()],
+        ..generator
+    };
+    assert!(missing.render(&nord).is_err());
+}
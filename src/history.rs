@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use colordata::traits::*;
+use colordata::Color;
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::config::Config;
+use crate::structures::Colors;
+use crate::structures::Wallpaper;
+use crate::utils;
+
+const HISTORY_FILE: &str = "history.json";
+const HISTORY_LIMIT: usize = 50;
+
+/// a theme that has been applied
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub source: Option<String>,
+    pub colors: Colors,
+    /// color16 .. color255 set in the input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended: Option<BTreeMap<u8, Color>>,
+    pub wallpaper: Option<String>,
+}
+
+impl HistoryEntry {
+    /// put the palette and the wallpaper of the entry into the config
+    /// the palette is used exactly as it was rendered
+    pub fn apply_to(&self, config: &mut Config) {
+        config.input = self.source.clone();
+        config.colors = Some(self.colors);
+        config.extended = self.extended.clone();
+        config.light = None;
+        config.reorder = None;
+        config.adjust = None;
+        if let Some(path) = &self.wallpaper {
+            // like --wallpaper, when the config has no [wallpaper]
+            let wal = config.wallpaper.get_or_insert_with(|| Wallpaper {
+                enable: Some(true),
+                backend: Some("auto".to_string()),
+                ..Wallpaper::default()
+            });
+            wal.set_path(path);
+        };
+    }
+}
+
+/// `colstract history`, `colstract history apply N` and `colstract undo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryCommand {
+    List,
+    Apply(usize),
+    Undo,
+}
+
+impl HistoryCommand {
+    /// remove the history subcommand from the arguments, if there is one
+    pub fn take(args: &mut Vec<String>) -> Option<Self> {
+        match args.get(1).map(String::as_str) {
+            Some("undo") => {
+                args.remove(1);
+                Some(HistoryCommand::Undo)
+            }
+            Some("history") => {
+                if args.get(2).map(String::as_str) == Some("apply") {
+                    let index = match args.get(3).and_then(|n| n.parse::<usize>().ok()) {
+                        Some(n) => n,
+                        None => {
+                            eprintln!("{}", "Usage: colstract history apply <N>".red());
+                            std::process::exit(1);
+                        }
+                    };
+                    args.drain(1..4);
+                    Some(HistoryCommand::Apply(index))
+                } else {
+                    args.remove(1);
+                    Some(HistoryCommand::List)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// the entries, oldest first
+pub fn load_history(output_dir: &Path) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(output_dir.join(HISTORY_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(history: &[HistoryEntry], output_dir: &Path) {
+    let json = serde_json::to_string_pretty(history).unwrap();
+    if let Err(e) = std::fs::write(output_dir.join(HISTORY_FILE), json) {
+        eprintln!("{}", format!("Could not save history: {}", e).red());
+    };
+}
+
+/// add the applied theme to the history, unless it is the same as the last one
+pub fn record(config: &Config, output_dir: &Path) {
+    let colors = match config.palette() {
+        Some(c) => c,
+        None => return,
+    };
+    let entry = HistoryEntry {
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        source: config.input.clone(),
+        colors,
+        extended: config.extended.clone(),
+        wallpaper: config.wallpaper.as_ref().and_then(|w| w.path.clone()),
+    };
+    let mut history = load_history(output_dir);
+    if let Some(last) = history.last() {
+        if last.colors == entry.colors
+            && last.extended == entry.extended
+            && last.wallpaper == entry.wallpaper
+        {
+            return;
+        };
+    };
+    history.push(entry);
+    if history.len() > HISTORY_LIMIT {
+        let excess = history.len() - HISTORY_LIMIT;
+        history.drain(..excess);
+    };
+    save_history(&history, output_dir);
+}
+
+/// the entry N themes back, 0 being the current one
+pub fn entry(output_dir: &Path, index: usize) -> Option<HistoryEntry> {
+    let history = load_history(output_dir);
+    let position = history.len().checked_sub(index + 1)?;
+    history.get(position).cloned()
+}
+
+/// drop the current theme from the history and return the one before it
+pub fn undo(output_dir: &Path) -> Option<HistoryEntry> {
+    let mut history = load_history(output_dir);
+    if history.len() < 2 {
+        return None;
+    };
+    history.pop();
+    save_history(&history, output_dir);
+    history.last().cloned()
+}
+
+/// a row of truecolor blocks, one per color
+pub fn swatches(colors: &Colors) -> String {
+    let mut row = String::new();
+    for color in std::iter::once(&colors.background).chain(colors.colors.iter()) {
+        row.push_str(&format!(
+            "\x1b[48;2;{}m  \x1b[0m",
+            color.rgb_stripped().replace(',', ";")
+        ));
+    }
+    row
+}
+
+pub fn print_history(output_dir: &Path) {
+    let history = load_history(output_dir);
+    if history.is_empty() {
+        eprintln!("{}", "No themes in the history yet".yellow());
+        return;
+    };
+    for (index, entry) in history.iter().rev().enumerate() {
+        println!(
+            "{:>3}  {}  {}  {}",
+            index,
+            utils::format_timestamp(entry.timestamp),
+            swatches(&entry.colors),
+            entry.source.clone().unwrap_or_default()
+        );
+        if let Some(wal) = &entry.wallpaper {
+            println!("     wallpaper: {}", wal);
+        };
+    }
+}
+
+#[test]
+fn history_test() {
+    let dir = std::env::temp_dir().join(format!("colstract-history-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    assert!(undo(&dir).is_none());
+
+    let mut config =
+        crate::builtin::load_builtin("nord", crate::derive::Strategy::default()).unwrap();
+    record(&config, &dir);
+    record(&config, &dir);
+    assert_eq!(load_history(&dir).len(), 1);
+    // the only theme cannot be undone, and stays
+    assert!(undo(&dir).is_none());
+    assert_eq!(load_history(&dir).len(), 1);
+
+    let red = |i: usize| colordata::Color::from_hex(&format!("#{:06x}", i));
+    for i in 0..HISTORY_LIMIT + 5 {
+        config.colors.as_mut().unwrap().colors[1] = red(i);
+        record(&config, &dir);
+    }
+    assert_eq!(load_history(&dir).len(), HISTORY_LIMIT);
+    assert_eq!(
+        entry(&dir, 0).unwrap().colors.colors[1],
+        red(HISTORY_LIMIT + 4)
+    );
+    assert_eq!(
+        entry(&dir, 1).unwrap().colors.colors[1],
+        red(HISTORY_LIMIT + 3)
+    );
+    assert!(entry(&dir, HISTORY_LIMIT - 1).is_some());
+    assert!(entry(&dir, HISTORY_LIMIT).is_none());
+
+    assert_eq!(undo(&dir).unwrap().colors.colors[1], red(HISTORY_LIMIT + 3));
+    assert_eq!(load_history(&dir).len(), HISTORY_LIMIT - 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn apply_to_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default()).unwrap();
+    let entry = HistoryEntry {
+        timestamp: 0,
+        source: Some("builtin:nord".to_string()),
+        colors: nord.colors.unwrap(),
+        extended: Some(std::iter::once((17, Color::from_hex("#123456"))).collect()),
+        wallpaper: Some("/home/user/Pictures/wall.png".to_string()),
+    };
+    let mut config = Config {
+        wallpaper: None,
+        light: Some(true),
+        ..Config::default()
+    };
+    entry.apply_to(&mut config);
+    assert_eq!(config.colors, nord.colors);
+    assert_eq!(config.extended, entry.extended);
+    assert_eq!(config.light, None);
+    assert_eq!(
+        config.wallpaper.and_then(|w| w.path),
+        Some("/home/user/Pictures/wall.png".to_string())
+    );
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use colordata::traits::*;
+use colordata::Color;
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// the `[icons]` section: directories of svg icons recolored to the palette
+/// every hex color in a fill, stroke or stop-color is replaced by the slot in `map`,
+/// or by the nearest color of the palette when it is not in `map`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Icons {
+    pub directories: Vec<String>,
+    pub map: BTreeMap<String, String>,
+    pub keep_unmapped: Option<bool>,
+}
+
+impl Icons {
+    pub fn from_toml(value: &toml::Value) -> Self {
+        let directories = value
+            .get("directories")
+            .and_then(|v| v.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|d| d.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let map = value
+            .get("map")
+            .and_then(|v| v.as_table())
+            .map(|t| {
+                t.iter()
+                    .filter_map(|(k, v)| Some((normalize(k)?, v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Icons {
+            directories,
+            map,
+            keep_unmapped: value.get("keep_unmapped").and_then(|v| v.as_bool()),
+        }
+    }
+
+    /// the color that replaces `hex`, None to leave it as it is
+    fn replacement(&self, hex: &str, colors: &Colors) -> Option<Color> {
+        let hex = normalize(hex)?;
+        if let Some(slot) = self.map.get(&hex) {
+            return match colors.slot(slot) {
+                Some(c) => Some(c),
+                None => {
+                    eprintln!(
+                        "{}",
+                        format!("Unknown color in icon map: {}", slot).yellow()
+                    );
+                    None
+                }
+            };
+        };
+        if self.keep_unmapped == Some(true) {
+            return None;
+        };
+        let color = Color::from_hex(&hex);
+        std::iter::once(&colors.background)
+            .chain(std::iter::once(&colors.foreground))
+            .chain(colors.colors.iter())
+            .min_by(|a, b| {
+                colorspace::delta_e(a, &color).total_cmp(&colorspace::delta_e(b, &color))
+            })
+            .copied()
+    }
+
+    /// the svg with its colors replaced, `color_regex` comes from `color_regex()`
+    pub fn recolor_svg(&self, svg: &str, colors: &Colors, color_regex: &regex::Regex) -> String {
+        color_regex
+            .replace_all(svg, |captures: &regex::Captures| {
+                let original = &captures[3];
+                match self.replacement(original, colors) {
+                    Some(color) => {
+                        // keep the alpha of #rrggbbaa colors
+                        let alpha = original.get(7..9).unwrap_or("");
+                        format!("{}{}{}{}", &captures[1], &captures[2], color.hex(), alpha)
+                    }
+                    None => captures[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// recolor every svg under the directories into `icons/<directory name>` in the output directory
+    /// directories with the same name get a numbered suffix, eg. `icons/Papirus-2`
+    pub fn render(&self, colors: &Colors, output_dir: &Path) {
+        let color_regex = color_regex();
+        let mut used: Vec<String> = Vec::new();
+        for directory in &self.directories {
+            let source = PathBuf::from(directory);
+            let base = match source.file_name() {
+                Some(n) => n.to_string_lossy().to_string(),
+                None => {
+                    eprintln!("{}", format!("Invalid icon directory: {}", directory).red());
+                    continue;
+                }
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while used.contains(&name) {
+                name = format!("{}-{}", base, suffix);
+                suffix += 1;
+            }
+            if name != base {
+                eprintln!(
+                    "{}",
+                    format!("Another icon directory is named {}, using {}", base, name).yellow()
+                );
+            };
+            used.push(name.clone());
+            let target = output_dir.join("icons").join(name);
+            let mut count = 0;
+            for file in collect_svgs(&source) {
+                let relative = file.strip_prefix(&source).unwrap_or(&file);
+                let destination = target.join(relative);
+                let result = std::fs::read_to_string(&file).and_then(|svg| {
+                    if let Some(parent) = destination.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    };
+                    std::fs::write(&destination, self.recolor_svg(&svg, colors, &color_regex))
+                });
+                match result {
+                    Ok(_) => count += 1,
+                    Err(e) => eprintln!(
+                        "{}",
+                        format!("Could not recolor {}: {}", file.display(), e).red()
+                    ),
+                };
+            }
+            println!(
+                "{}",
+                format!("recolored: {} icons into {}", count, target.display()).green()
+            );
+        }
+    }
+}
+
+/// fill="#fff", stroke: #a0a0a0, stop-color='#000000ff'
+fn color_regex() -> regex::Regex {
+    regex::Regex::new(
+        r#"(fill|stroke|stop-color)(\s*[:=]\s*["']?)(#[0-9a-fA-F]{8}|#[0-9a-fA-F]{6}|#[0-9a-fA-F]{3})\b"#,
+    )
+    .unwrap()
+}
+
+/// "#ABC", "#aabbcc" or "#aabbccdd" to "#aabbcc"
+fn normalize(hex: &str) -> Option<String> {
+    let digits = hex.trim().trim_start_matches('#').to_lowercase();
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    };
+    match digits.len() {
+        3 => Some(format!(
+            "#{}",
+            digits
+                .chars()
+                .flat_map(|c| std::iter::repeat(c).take(2))
+                .collect::<String>()
+        )),
+        6 | 8 => Some(format!("#{}", &digits[..6])),
+        _ => None,
+    }
+}
+
+/// every .svg file under `dir`, in subdirectories too, sorted by path
+fn collect_svgs(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_into(dir, &mut HashSet::new(), &mut files);
+    files.sort();
+    files
+}
+
+/// `visited` holds the canonical directories already read, so symlink loops end
+fn collect_into(dir: &Path, visited: &mut HashSet<PathBuf>, files: &mut Vec<PathBuf>) {
+    if !visited.insert(std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())) {
+        return;
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not read directory: {}\nError: {}", dir.display(), e).red()
+            );
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_into(&path, visited, files);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("svg") {
+            files.push(path);
+        };
+    }
+}
+
+#[test]
+fn icons_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let mut icons = Icons::default();
+    icons
+        .map
+        .insert("#ffffff".to_string(), "color4".to_string());
+    let color_regex = color_regex();
+    let svg = r##"<path fill="#FFF" stroke='#e00000' style="fill:#ffffff80;stroke:none"/>"##;
+    assert_eq!(
+        icons.recolor_svg(svg, &nord, &color_regex),
+        r##"<path fill="#81a1c1" stroke='#bf616a' style="fill:#81a1c180;stroke:none"/>"##
+    );
+    icons.keep_unmapped = Some(true);
+    assert!(icons
+        .recolor_svg(svg, &nord, &color_regex)
+        .contains("stroke='#e00000'"));
+    assert_eq!(normalize("#AbC"), Some("#aabbcc".to_string()));
+
+    let root = std::env::temp_dir().join(format!("colstract-icons-{}", std::process::id()));
+    for dir in &["a/Papirus", "b/Papirus"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("icon.svg"), svg).unwrap();
+    }
+    let icons = Icons {
+        directories: vec![
+            root.join("a/Papirus").display().to_string(),
+            root.join("b/Papirus").display().to_string(),
+        ],
+        ..Icons::default()
+    };
+    // a loop back to the top
+    std::os::unix::fs::symlink(root.join("a"), root.join("a/Papirus/loop")).unwrap();
+    assert_eq!(collect_svgs(&root.join("a")).len(), 1);
+    icons.render(&nord, &root.join("out"));
+    assert!(root.join("out/icons/Papirus/icon.svg").is_file());
+    assert!(root.join("out/icons/Papirus-2/icon.svg").is_file());
+    std::fs::remove_dir_all(&root).unwrap();
+}
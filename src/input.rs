@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::builtin;
+use crate::config::Config;
+use crate::derive;
+use crate::seed;
+use crate::themes;
+
+/// read an input source
+/// theme:<name> - a theme from the theme library
+/// builtin:<name> - one of the color schemes shipped with colstract
+/// seed:<#rrggbb>[:harmony][:dark|light] - a palette generated around one color
+/// anything else is a path to an Xresources or toml file,
+/// or the name of a saved theme when no such file exists
+/// missing colors are derived with `strategy`, unless the file, theme or scheme sets `derive` itself
+pub fn load_input(input: &str, strategy: derive::Strategy) -> Config {
+    if let Some(name) = input.strip_prefix("theme:") {
+        return match themes::load_theme(name, strategy) {
+            Some(config) => config,
+            None => panic!("no theme named {}", name),
+        };
+    };
+    if let Some(name) = input.strip_prefix("builtin:") {
+        return match builtin::load_builtin(name, strategy) {
+            Some(config) => config,
+            None => panic!("no built-in scheme named {}", name),
+        };
+    };
+    if let Some(spec) = input.strip_prefix("seed:") {
+        return match seed::parse(spec) {
+            Ok((color, harmony, light)) => Config {
+                colors: Some(seed::generate(&color, harmony, light)),
+                ..Config::default()
+            },
+            Err(e) => panic!("{}", e),
+        };
+    };
+    if !Path::new(input).exists() {
+        if let Some(config) = themes::load_theme(input, strategy) {
+            return config;
+        };
+    };
+    Config::from_file(input, strategy)
+}
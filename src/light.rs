@@ -0,0 +1,80 @@
+use colordata::Color;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// the lightness limit and minimum contrast against the background for the accents
+const BASE_ACCENT: (f64, f64) = (0.55, 3.0);
+const BRIGHT_ACCENT: (f64, f64) = (0.62, 3.0);
+
+/// a light counterpart of a dark palette; light palettes are returned as they are
+/// background and foreground trade places, keeping a hint of their hue,
+/// the black and white slots trade places and the accents are darkened in OKLCH
+/// until they are readable on the new background
+pub fn light_variant(colors: &Colors) -> Colors {
+    if !colorspace::is_dark(colors) {
+        return *colors;
+    };
+    let mut light = *colors;
+    light.background = with_lightness(&colors.foreground, 0.97, 0.02, &colors.background);
+    light.foreground = with_lightness(&colors.background, 0.30, 0.04, &colors.foreground);
+    light.cursor = with_lightness(&colors.background, 0.30, 0.04, &colors.cursor);
+
+    light.colors[0] = with_lightness(&colors.colors[0], 0.93, 0.02, &colors.colors[0]);
+    light.colors[7] = with_lightness(&colors.colors[7], 0.40, 0.03, &colors.colors[7]);
+    light.colors[8] = with_lightness(&colors.colors[8], 0.60, 0.03, &colors.colors[8]);
+    light.colors[15] = with_lightness(&colors.colors[15], 0.25, 0.03, &colors.colors[15]);
+
+    for index in (1..=6).chain(9..=14) {
+        let (max_lightness, min_contrast) = if index < 8 {
+            BASE_ACCENT
+        } else {
+            BRIGHT_ACCENT
+        };
+        light.colors[index] = darken_until_readable(
+            &colors.colors[index],
+            &light.background,
+            max_lightness,
+            min_contrast,
+        );
+    }
+    light
+}
+
+/// `hue_from` at the given OKLCH lightness with its chroma capped, keeping the alpha of `alpha_from`
+fn with_lightness(hue_from: &Color, lightness: f64, max_chroma: f64, alpha_from: &Color) -> Color {
+    let (_, chroma, hue) = colorspace::to_oklch(hue_from);
+    colorspace::from_oklch(lightness, chroma.min(max_chroma), hue, alpha_from)
+}
+
+/// lower the lightness of `color` until it reaches `min_contrast` against `background`
+pub fn darken_until_readable(
+    color: &Color,
+    background: &Color,
+    max_lightness: f64,
+    min_contrast: f64,
+) -> Color {
+    let (lightness, chroma, hue) = colorspace::to_oklch(color);
+    let mut lightness = lightness.min(max_lightness);
+    loop {
+        let candidate = colorspace::from_oklch(lightness, chroma, hue, color);
+        if colorspace::contrast_ratio(&candidate, background) >= min_contrast || lightness <= 0.2 {
+            return candidate;
+        };
+        lightness -= 0.01;
+    }
+}
+
+#[test]
+fn light_variant_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let light = light_variant(&nord);
+    assert!(!colorspace::is_dark(&light));
+    for index in (1..=6).chain(9..=14) {
+        assert!(colorspace::contrast_ratio(&light.colors[index], &light.background) >= 3.0);
+    }
+    assert_eq!(light_variant(&light), light);
+}
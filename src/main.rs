@@ -1,7 +1,30 @@
+pub mod adjust;
+pub mod builtin;
+pub mod check;
+pub mod colorspace;
 pub mod config;
+pub mod contrast;
+pub mod daemon;
+pub mod derive;
+pub mod extended;
+pub mod generate;
+pub mod history;
+pub mod icons;
+pub mod input;
+pub mod light;
+pub mod preview;
+pub mod recolor;
 pub mod render_template;
+pub mod reorder;
+pub mod schedule;
+pub mod seed;
+pub mod sequences;
+pub mod sheet;
+pub mod state;
 pub mod structures;
+pub mod themes;
 pub mod utils;
+pub mod watch;
 
 use std::env;
 use std::path::Path;
@@ -13,11 +36,62 @@ use argumentparser::Parser;
 use text_colorizer::Colorize;
 
 use crate::config::Config;
+use crate::daemon::Request;
+use crate::daemon::Response;
+use crate::generate::Generator;
+use crate::history::HistoryCommand;
+use crate::render_template::compile_templates;
+use crate::render_template::render_compiled;
 use crate::render_template::render_template;
+use crate::sequences::build_sequences;
+use crate::sequences::save_sequences;
+use crate::sequences::send_sequences;
+use crate::structures::Colors;
 use crate::structures::Wallpaper;
+use crate::themes::ThemeCommand;
+
+/// how often the daemon checks the schedule
+const SCHEDULE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
 
 fn main() {
-    let mut parser = Parser::with_capacity(5);
+    let mut arguments = std::env::args().collect::<Vec<String>>();
+    if arguments.get(1).map(String::as_str) == Some("msg") {
+        send_message(&arguments[2..]);
+        return;
+    };
+    let daemon_mode = arguments.get(1).map(String::as_str) == Some("daemon");
+    if daemon_mode {
+        arguments.remove(1);
+    };
+    let check_mode = arguments.get(1).map(String::as_str) == Some("check");
+    if check_mode {
+        arguments.remove(1);
+    };
+    let json_output = check_mode && utils::take_flag(&mut arguments, &["--json"]);
+    let preview_mode = arguments.get(1).map(String::as_str) == Some("preview");
+    if preview_mode {
+        arguments.remove(1);
+    };
+    let with_thumbnail = preview_mode && utils::take_flag(&mut arguments, &["--thumbnail"]);
+    let schedule_tick = arguments.get(1).map(String::as_str) == Some("schedule");
+    if schedule_tick {
+        if arguments.get(2).map(String::as_str) != Some("tick") {
+            eprintln!("{}", "Usage: colstract schedule tick".red());
+            std::process::exit(1);
+        };
+        arguments.drain(1..3);
+    };
+    let mut no_sequences = utils::take_flag(&mut arguments, &["--no-sequences", "-n"]);
+    let watch_mode = utils::take_flag(&mut arguments, &["--watch"]);
+    let restore_mode = utils::take_flag(&mut arguments, &["--restore"]);
+    let light_mode = utils::take_flag(&mut arguments, &["--light", "-l"]);
+    let reorder_mode = utils::take_flag(&mut arguments, &["--reorder"]);
+    utils::set_verbose(utils::take_flag(&mut arguments, &["--verbose", "-v"]));
+    let generator = Generator::take(&mut arguments);
+    let history_command = HistoryCommand::take(&mut arguments);
+    let theme_command = ThemeCommand::take(&mut arguments);
+
+    let mut parser = Parser::with_capacity(9);
     parser.add_argument(
         Argument::with_type("word")
             .name("config")
@@ -32,6 +106,13 @@ fn main() {
             .invoke_with("-i")
             .required(false),
     );
+    parser.add_argument(
+        Argument::with_type("word")
+            .name("theme")
+            .invoke_with("--theme")
+            .invoke_with("-t")
+            .required(false),
+    );
     parser.add_argument(
         Argument::with_type("word")
             .name("output")
@@ -53,39 +134,522 @@ fn main() {
             .invoke_with("-W")
             .required(false),
     );
+    parser.add_argument(
+        Argument::with_type("word")
+            .name("recolor")
+            .invoke_with("--recolor")
+            .required(false),
+    );
+    parser.add_argument(
+        Argument::with_type("word")
+            .name("min-contrast")
+            .invoke_with("--min-contrast")
+            .required(false),
+    );
+    parser.add_argument(
+        Argument::with_type("word")
+            .name("adjust")
+            .invoke_with("--adjust")
+            .required(false),
+    );
 
     // started parsing the arguments
-    let parsed_arguments = parser.parse_arguments(&std::env::args().collect::<Vec<String>>());
+    let parsed_arguments = parser.parse_arguments(&arguments);
     drop(parser);
-    let mut config = create_config(&parsed_arguments);
-    config = compose_config(&parsed_arguments, config);
-    drop(parsed_arguments);
+    if check_mode {
+        let report = check::check(&load_palette(&parsed_arguments, light_mode, reorder_mode).1);
+        if json_output {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        } else {
+            check::print_report(&report);
+        };
+        return;
+    };
+    if preview_mode {
+        let (config, colors) = load_palette(&parsed_arguments, light_mode, reorder_mode);
+        // --output names the sheet to write instead of the output directory
+        let sheet_path = match parsed_arguments.get_value("output") {
+            Some(argumentparser::Value::Word(path)) => PathBuf::from(path),
+            _ => {
+                print!("{}", preview::render(&colors, preview::truecolor()));
+                return;
+            }
+        };
+        let wallpaper = if with_thumbnail {
+            match config.wallpaper.and_then(|w| w.path).map(PathBuf::from) {
+                Some(path) if path.is_file() => Some(path),
+                _ => {
+                    eprintln!(
+                        "{}",
+                        "Warning: no wallpaper image for the thumbnail".yellow()
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        match sheet::write_sheet(&colors, wallpaper.as_deref(), &sheet_path) {
+            Ok(_) => println!("{}", format!("saved: {}", sheet_path.display()).green()),
+            Err(e) => {
+                eprintln!("{}", format!("Could not write the sheet: {}", e).red());
+                std::process::exit(1);
+            }
+        };
+        return;
+    };
+    let (mut config, output_directory) = if restore_mode {
+        // only the output directory is needed, the inputs are not read again
+        let base = compose_config(&parsed_arguments, create_config(&parsed_arguments));
+        let output_directory = get_output_directory(&base);
+        let state = match state::load_state(&output_directory) {
+            Some(s) => s,
+            None => {
+                eprintln!("{}", "Nothing to restore".red());
+                std::process::exit(1);
+            }
+        };
+        no_sequences = no_sequences || state.no_sequences;
+        (state.config, output_directory)
+    } else if let Some(command) = theme_command {
+        let mut config = compose_config(&parsed_arguments, create_config(&parsed_arguments));
+        let output_directory = get_output_directory(&config);
+        match command {
+            ThemeCommand::List => {
+                themes::print_themes();
+                return;
+            }
+            ThemeCommand::ListBuiltin => {
+                builtin::print_builtin();
+                return;
+            }
+            ThemeCommand::Save(name) => {
+                // the theme that was applied last, or the one the config describes
+                let current = match state::load_state(&output_directory) {
+                    Some(state) => state.config,
+                    None => {
+                        exit_on_error(resolve_colors(&mut config));
+                        config
+                    }
+                };
+                match themes::save_theme(&name, &current) {
+                    Ok(path) => println!("{}", format!("saved: {}", path.display()).green()),
+                    Err(e) => {
+                        eprintln!("{}", format!("Could not save theme: {}", e).red());
+                        std::process::exit(1);
+                    }
+                };
+                return;
+            }
+            ThemeCommand::Remove(name) => {
+                match themes::remove_theme(&name) {
+                    Ok(_) => println!("{}", format!("removed: {}", name).green()),
+                    Err(e) => {
+                        eprintln!("{}", format!("Could not remove theme: {}", e).red());
+                        std::process::exit(1);
+                    }
+                };
+                return;
+            }
+            ThemeCommand::Apply(name) => {
+                config.input = Some(format!("theme:{}", name));
+                config.colors = None;
+                exit_on_error(resolve_colors(&mut config));
+            }
+        };
+        (config, output_directory)
+    } else if let Some(command) = history_command {
+        let mut config = compose_config(&parsed_arguments, create_config(&parsed_arguments));
+        let output_directory = get_output_directory(&config);
+        let entry = match command {
+            HistoryCommand::List => {
+                history::print_history(&output_directory);
+                return;
+            }
+            HistoryCommand::Apply(index) => history::entry(&output_directory, index),
+            HistoryCommand::Undo => history::undo(&output_directory),
+        };
+        match entry {
+            Some(e) => e.apply_to(&mut config),
+            None => {
+                eprintln!("{}", "No such theme in the history".red());
+                std::process::exit(1);
+            }
+        };
+        (config, output_directory)
+    } else if schedule_tick {
+        let mut config = compose_config(&parsed_arguments, create_config(&parsed_arguments));
+        let output_directory = get_output_directory(&config);
+        let period = match follow_schedule(&mut config, &output_directory, false) {
+            Some(p) => p,
+            None => {
+                println!("{}", "nothing to switch".green());
+                return;
+            }
+        };
+        exit_on_error(resolve_colors(&mut config));
+        schedule::save_period(period, &output_directory);
+        (config, output_directory)
+    } else if daemon_mode {
+        // before anything is rendered or applied
+        if daemon::is_running() {
+            eprintln!("{}", "A colstract daemon is already running".red());
+            std::process::exit(1);
+        };
+        let config = exit_on_error(load_scheduled_config(&parsed_arguments));
+        let output_directory = get_output_directory(&config);
+        (config, output_directory)
+    } else {
+        let config = exit_on_error(load_config(&parsed_arguments));
+        let output_directory = get_output_directory(&config);
+        (config, output_directory)
+    };
+    if light_mode {
+        config.light = Some(true);
+    };
+    if reorder_mode {
+        config.reorder = Some(true);
+    };
+    if let Some(generator) = &generator {
+        let generated = match config.palette() {
+            Some(colors) => generator
+                .render(&colors)
+                .and_then(|image| generate::save(&image, &output_directory)),
+            None => Err("no colors loaded".to_string()),
+        };
+        match generated {
+            Ok(path) => {
+                println!("{}", format!("generated: {}", path.display()).green());
+                set_wallpaper_path(&mut config, &path.display().to_string());
+            }
+            Err(e) => {
+                eprintln!("{}", format!("Could not generate a wallpaper: {}", e).red());
+                std::process::exit(1);
+            }
+        };
+    };
     let template_containers = [
         PathBuf::from("/usr")
             .join("share")
             .join("colstract")
             .join("templates"),
-        get_config_home().join("colstract").join("templates"),
+        utils::get_config_home().join("colstract").join("templates"),
     ];
     let mut templates_paths = Vec::new();
-    for path in template_containers {
-        collect_templates(&mut templates_paths, &path);
+    for path in &template_containers {
+        collect_templates(&mut templates_paths, path);
+    }
+
+    // the `[wallpaper]` path before an image is picked from it, to notice when a reload changes it
+    let mut wallpaper_source = config.wallpaper.as_ref().and_then(|w| w.path.clone());
+    if let Some(wal) = &mut config.wallpaper {
+        wal.select_image(&output_directory);
+    };
+
+    render_templates(&config, &templates_paths, &output_directory);
+    // restoring or going back in the history applies a theme that is recorded already
+    let record = !restore_mode && history_command.is_none();
+    apply_theme(&config, &output_directory, no_sequences, record);
+
+    if daemon_mode {
+        let mut registry = compile_templates(&templates_paths);
+        if config.schedule.is_some() {
+            daemon::tick_every(SCHEDULE_INTERVAL, Request::ScheduleTick);
+        };
+        daemon::serve(|request| {
+            match request {
+                Request::SetTheme { theme } => {
+                    // resolved on a copy, a theme that fails to load keeps the current palette
+                    let mut next = config.clone();
+                    next.input = Some(theme);
+                    next.colors = None;
+                    if let Err(e) = resolve_colors(&mut next) {
+                        return Response::error(&e);
+                    };
+                    config = next;
+                }
+                Request::SetWallpaper { path } => {
+                    set_wallpaper_path(&mut config, &path);
+                    wallpaper_source = Some(path);
+                    if let Some(wal) = &mut config.wallpaper {
+                        wal.select_image(&output_directory);
+                    };
+                }
+                Request::ScheduleTick => {
+                    let mut next = config.clone();
+                    let period = match follow_schedule(&mut next, &output_directory, false) {
+                        Some(p) => p,
+                        None => return Response::ok(),
+                    };
+                    if let Err(e) = resolve_colors(&mut next) {
+                        return Response::error(&e);
+                    };
+                    schedule::save_period(period, &output_directory);
+                    config = next;
+                    if let Some(wal) = &mut config.wallpaper {
+                        wal.select_image(&output_directory);
+                    };
+                }
+                Request::Reload => {
+                    config = match load_scheduled_config(&parsed_arguments) {
+                        Ok(c) => c,
+                        Err(e) => return Response::error(&e),
+                    };
+                    if light_mode {
+                        config.light = Some(true);
+                    };
+                    if reorder_mode {
+                        config.reorder = Some(true);
+                    };
+                    reselect_wallpaper(&mut config, &mut wallpaper_source, &output_directory);
+                    templates_paths.clear();
+                    for path in &template_containers {
+                        collect_templates(&mut templates_paths, path);
+                    }
+                    registry = compile_templates(&templates_paths);
+                }
+                Request::GetPalette => {
+                    return match &config.palette() {
+                        Some(colors) => Response {
+                            palette: Some(daemon::palette(colors)),
+                            ..Response::ok()
+                        },
+                        None => Response::error("no colors loaded"),
+                    };
+                }
+            };
+            render_compiled_templates(&config, &registry, &output_directory);
+            apply_theme(&config, &output_directory, no_sequences, true);
+            Response::ok()
+        });
+    } else if watch_mode {
+        let mut files = vec![get_config_path(&parsed_arguments)];
+        // builtin:, theme:, seed: and random inputs are not files
+        if let Some(inp) = config.input.as_ref().filter(|inp| Path::new(inp).is_file()) {
+            files.push(PathBuf::from(inp));
+        };
+        watch::watch(&files, &template_containers, |changed| {
+            let sources_changed = changed.iter().any(|path| files.contains(path));
+            if sources_changed {
+                println!("{}", "sources changed, reloading".green());
+                // a config saved halfway must not end the session
+                let reloaded = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    load_config(&parsed_arguments)
+                }));
+                config = match reloaded {
+                    Ok(Ok(c)) => c,
+                    Ok(Err(e)) => {
+                        eprintln!("{}", format!("{}, keeping the last config", e).red());
+                        return;
+                    }
+                    Err(_) => {
+                        eprintln!("{}", "Could not reload, keeping the last config".yellow());
+                        return;
+                    }
+                };
+                if light_mode {
+                    config.light = Some(true);
+                };
+                if reorder_mode {
+                    config.reorder = Some(true);
+                };
+                reselect_wallpaper(&mut config, &mut wallpaper_source, &output_directory);
+                templates_paths.clear();
+                for path in &template_containers {
+                    collect_templates(&mut templates_paths, path);
+                }
+                render_templates(&config, &templates_paths, &output_directory);
+            } else {
+                let affected = changed
+                    .iter()
+                    .filter(|path| path.is_file())
+                    .cloned()
+                    .collect::<Vec<PathBuf>>();
+                if affected.is_empty() {
+                    return;
+                };
+                render_templates(&config, &affected, &output_directory);
+            };
+            apply_theme(&config, &output_directory, no_sequences, sources_changed);
+        });
+    };
+}
+
+/// pick the wallpaper after a reload: the image picked before is kept
+/// unless the `[wallpaper]` path changed, eg. to another directory
+fn reselect_wallpaper(config: &mut Config, source: &mut Option<String>, output_directory: &Path) {
+    let path = config.wallpaper.as_ref().and_then(|w| w.path.clone());
+    if let Some(wal) = &mut config.wallpaper {
+        if &path == source {
+            wal.reuse_image(output_directory);
+        } else {
+            wal.select_image(output_directory);
+        };
+    };
+    *source = path;
+}
+
+/// read the config and apply the command line arguments on top of it
+fn load_config(parsed_arguments: &ParsedArguments) -> Result<Config, String> {
+    let mut config = create_config(parsed_arguments);
+    config = compose_config(parsed_arguments, config);
+    resolve_colors(&mut config)?;
+    Ok(config)
+}
+
+/// the value, or the error printed and colstract ended
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// the config and the palette it describes, for commands that only look at the colors
+fn load_palette(
+    parsed_arguments: &ParsedArguments,
+    light_mode: bool,
+    reorder_mode: bool,
+) -> (Config, Colors) {
+    let mut config = exit_on_error(load_config(parsed_arguments));
+    if light_mode {
+        config.light = Some(true);
+    };
+    if reorder_mode {
+        config.reorder = Some(true);
+    };
+    match config.palette() {
+        Some(c) => (config, c),
+        None => {
+            eprintln!("{}", "No colors loaded".red());
+            std::process::exit(1);
+        }
     }
+}
 
+/// like `load_config`, with the theme and wallpaper of the current period of the schedule
+fn load_scheduled_config(parsed_arguments: &ParsedArguments) -> Result<Config, String> {
+    let mut config = compose_config(parsed_arguments, create_config(parsed_arguments));
+    let output_directory = output_directory_path(&config);
+    let period = follow_schedule(&mut config, &output_directory, true);
+    resolve_colors(&mut config)?;
+    if let Some(period) = period {
+        schedule::save_period(period, &output_directory);
+    };
+    Ok(config)
+}
+
+/// point the config at the theme and wallpaper of the current period of the schedule
+/// without a theme for the period, the light variant is switched on and off instead
+/// returns the period, to be saved once its colors resolved, or None when there is
+/// nothing to do: no schedule, or the period has not changed since it was last applied (unless `force`)
+fn follow_schedule(
+    config: &mut Config,
+    output_directory: &Path,
+    force: bool,
+) -> Option<schedule::Period> {
+    let schedule = match &config.schedule {
+        Some(s) => s.clone(),
+        None => return None,
+    };
+    let period = match schedule.current_period() {
+        Some(p) => p,
+        None => {
+            eprintln!(
+                "{}",
+                "The schedule needs light_from and dark_from, or latitude and longitude".red()
+            );
+            return None;
+        }
+    };
+    if !force && schedule::last_period(output_directory) == Some(period) {
+        return None;
+    };
+    println!("{}", format!("schedule: {}", period.as_str()).green());
+    match schedule.input_for(period) {
+        Some(theme) => {
+            config.input = Some(theme.clone());
+            config.colors = None;
+        }
+        None => config.light = Some(period == schedule::Period::Light),
+    };
+    if let Some(path) = schedule.wallpaper_for(period) {
+        set_wallpaper_path(config, path);
+    };
+    Some(period)
+}
+
+/// read the colors from the input unless the config has them already
+/// a theme input also brings its wallpaper and template variables along
+fn resolve_colors(config: &mut Config) -> Result<(), String> {
+    if let Some(inp) = config.input.clone() {
+        if config.colors.is_none() && themes::is_random(&inp) {
+            let current = state::load_state(&output_directory_path(config)).map(|s| s.config);
+            match themes::random_theme(&inp, current.as_ref()) {
+                Some(source) => {
+                    println!("{}", format!("picked theme: {}", source).green());
+                    config.input = Some(source);
+                }
+                None => return Err("No theme to pick from".to_string()),
+            };
+        };
+    };
     if let Some(inp) = &(config.input) {
         if config.colors.is_none() {
-            let new_config = Config::from(inp);
+            let strategy = config.derive.unwrap_or_default();
+            let new_config = input::load_input(inp, strategy);
+            derive::print_report(&new_config.derived, new_config.derive.unwrap_or(strategy));
             let colors = new_config.colors;
             config.colors = colors;
+            config.extended = new_config.extended;
+            if let Some(path) = new_config.wallpaper.and_then(|w| w.path) {
+                set_wallpaper_path(config, &path);
+            };
+            if let Some(variables) = new_config.variables {
+                config
+                    .variables
+                    .get_or_insert_with(Default::default)
+                    .extend(variables);
+            };
         };
     };
+    Ok(())
+}
 
-    let output_directory = config.output_directory.clone().map_or_else(
-        || {
+/// send the words after `msg` to a running daemon and print the response
+fn send_message(words: &[String]) {
+    let request = match Request::from_args(words) {
+        Some(r) => r,
+        None => {
             eprintln!(
                 "{}",
-                "Warning: no output dir configured, using default".yellow()
+                "Usage: colstract msg set-theme <input> | set-wallpaper <path> | reload | get-palette | schedule-tick"
+                    .red()
             );
+            std::process::exit(1);
+        }
+    };
+    match daemon::send(&request) {
+        Ok(response) => {
+            println!("{}", serde_json::to_string_pretty(&response).unwrap());
+            if !response.ok {
+                std::process::exit(1);
+            };
+        }
+        Err(e) => {
+            eprintln!("{}", format!("Could not reach the daemon: {}", e).red());
+            std::process::exit(1);
+        }
+    };
+}
+
+/// the configured output directory, or ~/.cache/colstract
+fn output_directory_path(config: &Config) -> PathBuf {
+    config.output_directory.clone().map_or_else(
+        || {
             let home = match env::var("HOME") {
                 Ok(val) => PathBuf::from(val),
                 Err(_) => {
@@ -97,7 +661,17 @@ fn main() {
             home.join(".cache").join("colstract")
         },
         PathBuf::from,
-    );
+    )
+}
+
+fn get_output_directory(config: &Config) -> PathBuf {
+    if config.output_directory.is_none() {
+        eprintln!(
+            "{}",
+            "Warning: no output dir configured, using default".yellow()
+        );
+    };
+    let output_directory = output_directory_path(config);
 
     if !output_directory.exists() {
         eprintln!("{}", "Warning: output directory does not exist".yellow());
@@ -109,8 +683,11 @@ fn main() {
             }
         };
     };
+    output_directory
+}
 
-    let mut data = serde_json::from_str(&config.to_json()).unwrap();
+fn render_templates(config: &Config, templates_paths: &[PathBuf], output_directory: &Path) {
+    let mut data = config.template_data();
     for item in templates_paths {
         let name = match item.file_name() {
             Some(va) => match va.to_str() {
@@ -125,7 +702,7 @@ fn main() {
                 continue;
             }
         };
-        match render_template(name, &item, &output_directory, &mut data) {
+        match render_template(name, item, output_directory, &mut data) {
             Ok(_) => {
                 println!("{}", format!("rendered: {}", name).green());
             }
@@ -138,14 +715,78 @@ fn main() {
             }
         };
     }
+}
 
-    if let Some(wal) = &config.wallpaper {
-        if wal.command.is_some() {
-            if let Some(true) = &wal.enable {
-                wal.apply_wallpaper();
+fn render_compiled_templates(
+    config: &Config,
+    registry: &handlebars::Handlebars,
+    output_directory: &Path,
+) {
+    let data = config.template_data();
+    let mut names = registry
+        .get_templates()
+        .keys()
+        .cloned()
+        .collect::<Vec<String>>();
+    names.sort();
+    for name in names {
+        match render_compiled(registry, &name, output_directory, &data) {
+            Ok(_) => {
+                println!("{}", format!("rendered: {}", name).green());
             }
-        }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("An error occured rendering: {}\nError: {}", &name, e)
+                );
+            }
+        };
+    }
+}
+
+/// the steps that run after rendering: terminal sequences, the wallpaper and the icons
+/// the config is saved afterwards so that `--restore` can apply it again,
+/// and added to the history when `record` is set, ie. when a new theme was resolved
+fn apply_theme(config: &Config, output_directory: &Path, no_sequences: bool, record: bool) {
+    if let Some(colors) = &config.palette() {
+        let sequences = build_sequences(colors, config.extended.as_ref());
+        match save_sequences(&sequences, output_directory) {
+            Ok(_) => println!("{}", "saved: sequences".green()),
+            Err(e) => eprintln!("{}", format!("Could not save sequences: {}", e).red()),
+        };
+        if !no_sequences {
+            send_sequences(&sequences);
+        };
+    };
+
+    // recolor only when the wallpaper is actually going to be set
+    let applied = config
+        .wallpaper
+        .as_ref()
+        .filter(|wal| wal.enable == Some(true) && (wal.command.is_some() || wal.backend.is_some()));
+    if let Some(wal) = applied {
+        let wal = match &config.palette() {
+            Some(colors) => wal.recolored(colors, output_directory),
+            None => wal.clone(),
+        };
+        wal.apply_wallpaper(config.palette().as_ref());
     }
+
+    if let (Some(icons), Some(colors)) = (&config.icons, &config.palette()) {
+        icons.render(colors, output_directory);
+    };
+
+    if let (Some(true), Some(colors)) = (config.reorder, &config.colors) {
+        reorder::print_report(colors);
+    };
+    if let Some(colors) = &config.palette() {
+        contrast::print_report(&config.contrast_adjustments(), &colors.background);
+    };
+
+    state::save_state(config, no_sequences, output_directory);
+    if record {
+        history::record(config, output_directory);
+    };
 }
 
 fn compose_config(parsed_arguments: &ParsedArguments, mut config: Config) -> Config {
@@ -154,24 +795,56 @@ fn compose_config(parsed_arguments: &ParsedArguments, mut config: Config) -> Con
         config.colors = None;
     };
 
+    if let Some(argumentparser::Value::Word(theme)) = parsed_arguments.get_value("theme") {
+        // a plain name is a saved theme, anything else is an input like builtin:nord or random
+        config.input = if themes::is_random(theme) || theme.contains(':') {
+            Some(theme.clone())
+        } else {
+            Some(format!("theme:{}", theme))
+        };
+        config.colors = None;
+    };
+
+    if let Some(argumentparser::Value::Word(ratio)) = parsed_arguments.get_value("min-contrast") {
+        match ratio.parse::<f64>() {
+            Ok(r) => {
+                let settings = config.contrast.get_or_insert_with(Default::default);
+                settings.enable = Some(true);
+                settings.min_ratio = Some(r);
+            }
+            Err(_) => {
+                eprintln!("{}", format!("Invalid contrast ratio: {}", ratio).red());
+                std::process::exit(1);
+            }
+        };
+    };
+
+    if let Some(argumentparser::Value::Word(list)) = parsed_arguments.get_value("adjust") {
+        match adjust::Step::parse_list(list) {
+            Ok(steps) => config
+                .adjust
+                .get_or_insert_with(Default::default)
+                .steps
+                .extend(steps),
+            Err(e) => {
+                eprintln!("{}", e.red());
+                std::process::exit(1);
+            }
+        };
+    };
+
     if let Some(argumentparser::Value::Word(out)) = parsed_arguments.get_value("output") {
         config.output_directory = Some(out.clone());
     };
 
     if let Some(argumentparser::Value::Word(walpath)) = parsed_arguments.get_value("wallpaper") {
-        match config.wallpaper {
-            Some(mut wal) => {
-                wal.set_path(walpath);
-                config.wallpaper = Some(wal);
-            }
-            None => {
-                let wal = Wallpaper {
-                    enable: Some(true),
-                    path: Some(walpath.clone()),
-                    command: None,
-                };
-                config.wallpaper = Some(wal);
-            }
+        set_wallpaper_path(&mut config, walpath);
+    };
+
+    if let Some(argumentparser::Value::Word(mode)) = parsed_arguments.get_value("recolor") {
+        match &mut config.wallpaper {
+            Some(wal) => wal.recolor = Some(mode.clone()),
+            None => eprintln!("{}", "Warning: no wallpaper to recolor".yellow()),
         };
     };
 
@@ -188,6 +861,7 @@ fn compose_config(parsed_arguments: &ParsedArguments, mut config: Config) -> Con
                     enable: Some(false),
                     path: None,
                     command: Some(com.clone()),
+                    ..Wallpaper::default()
                 };
                 config.wallpaper = Some(wal);
             }
@@ -196,44 +870,43 @@ fn compose_config(parsed_arguments: &ParsedArguments, mut config: Config) -> Con
     config
 }
 
-fn create_config(parsed_arguments: &ParsedArguments) -> Config {
-    let config_home = get_config_home();
-    let config_toml = config_home.join("colstract").join("config.toml");
-    let config = match parsed_arguments.get_value("config") {
-        Some(val) => {
-            if let argumentparser::Value::Word(c) = val {
-                Config::from(c)
-            } else if config_toml.exists() {
-                Config::from(std::fs::read_to_string(&config_toml).unwrap())
-            } else {
-                eprintln!("{}", "No config files found; using defaults".yellow());
-                Config::default()
-            }
+fn set_wallpaper_path(config: &mut Config, walpath: &str) {
+    match config.wallpaper.take() {
+        Some(mut wal) => {
+            wal.set_path(walpath);
+            config.wallpaper = Some(wal);
         }
         None => {
-            if config_toml.exists() {
-                Config::from(std::fs::read_to_string(&config_toml).unwrap())
-            } else {
-                eprintln!("{}", "No config files found; using defaults".yellow());
-                Config::default()
-            }
+            let wal = Wallpaper {
+                enable: Some(true),
+                path: Some(walpath.to_string()),
+                command: None,
+                backend: Some("auto".to_string()),
+                ..Wallpaper::default()
+            };
+            config.wallpaper = Some(wal);
         }
     };
-    config
 }
 
-fn get_config_home() -> PathBuf {
-    let home = match env::var("HOME") {
-        Ok(val) => PathBuf::from(val),
-        Err(_) => {
-            eprintln!("{}", "Could not find home directory".red());
-            eprintln!("{}", "Cannot continue, exiting".red());
-            std::process::exit(1);
-        }
-    };
-    match env::var("XDG_CONFIG_HOME") {
-        Ok(val) => PathBuf::from(val),
-        Err(_) => home.join(".config"),
+fn get_config_path(parsed_arguments: &ParsedArguments) -> PathBuf {
+    match parsed_arguments.get_value("config") {
+        Some(argumentparser::Value::Word(c)) => PathBuf::from(c),
+        _ => utils::get_config_home()
+            .join("colstract")
+            .join("config.toml"),
+    }
+}
+
+fn create_config(parsed_arguments: &ParsedArguments) -> Config {
+    let config_toml = get_config_path(parsed_arguments);
+    if config_toml.exists() {
+        let config = Config::from_file(&config_toml, derive::Strategy::default());
+        derive::print_report(&config.derived, config.derive.unwrap_or_default());
+        config
+    } else {
+        eprintln!("{}", "No config files found; using defaults".yellow());
+        Config::default()
     }
 }
 
@@ -0,0 +1,148 @@
+use colordata::traits::*;
+use colordata::Color;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+/// the channel levels of the 6x6x6 cube in the xterm 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// whether the terminal says it can show 24 bit color
+pub fn truecolor() -> bool {
+    match std::env::var("COLORTERM") {
+        Ok(val) => val == "truecolor" || val == "24bit",
+        Err(_) => false,
+    }
+}
+
+/// the closest color of the xterm 256 color palette, from the cube or the gray ramp
+pub fn ansi256(color: &Color) -> u8 {
+    let (r, g, b) = colorspace::rgb(color);
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    // the ramp goes from 8 to 238 in steps of 10
+    let average = (r as i32 + g as i32 + b as i32) / 3;
+    let step = ((average - 8 + 5) / 10).clamp(0, 23);
+    let gray = (8 + step * 10) as u8;
+    if distance((gray, gray, gray)) < distance(cube) {
+        232 + step as u8
+    } else {
+        (16 + 36 * ri + 6 * gi + bi) as u8
+    }
+}
+
+/// the escape sequence that sets the background, or the foreground when `foreground`
+fn paint(color: &Color, foreground: bool, truecolor: bool) -> String {
+    let target = if foreground { 38 } else { 48 };
+    if truecolor {
+        format!(
+            "\x1b[{};2;{}m",
+            target,
+            color.rgb_stripped().replace(',', ";")
+        )
+    } else {
+        format!("\x1b[{};5;{}m", target, ansi256(color))
+    }
+}
+
+/// a block of `width` cells in the color
+fn block(color: &Color, width: usize, truecolor: bool) -> String {
+    format!(
+        "{}{}\x1b[0m",
+        paint(color, false, truecolor),
+        " ".repeat(width)
+    )
+}
+
+/// a row of swatches with their slot names above and hex values below
+fn swatch_row(colors: &[(String, Color)], truecolor: bool) -> String {
+    let mut names = String::new();
+    let mut blocks = String::new();
+    let mut labels = String::new();
+    for (name, color) in colors {
+        names.push_str(&format!("{:<9} ", name));
+        blocks.push_str(&block(color, 9, truecolor));
+        blocks.push(' ');
+        labels.push_str(&format!("{:<9} ", color.hex()));
+    }
+    format!("{}\n{}\n{}\n{}\n", names, blocks, blocks, labels)
+}
+
+/// the palette as terminal output: background, foreground and cursor,
+/// the normal and bright ansi colors, and some text on the background
+pub fn render(colors: &Colors, truecolor: bool) -> String {
+    let mut output = String::new();
+    output.push_str(&swatch_row(
+        &[
+            ("background".to_string(), colors.background),
+            ("foreground".to_string(), colors.foreground),
+            ("cursor".to_string(), colors.cursor),
+        ],
+        truecolor,
+    ));
+    output.push('\n');
+    for (row, slots) in colors.colors.chunks(8).enumerate() {
+        let named = slots
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (format!("color{}", row * 8 + i), *c))
+            .collect::<Vec<(String, Color)>>();
+        output.push_str(&swatch_row(&named, truecolor));
+    }
+    output.push('\n');
+
+    let background = paint(&colors.background, false, truecolor);
+    let text = |color: &Color, s: &str| format!("{}{}", paint(color, true, truecolor), s);
+    let sample = [
+        format!(
+            "{}{} ",
+            text(&colors.colors[2], "user@host"),
+            text(&colors.foreground, ":~$ ls")
+        ),
+        format!(
+            "{}  {}  {}  {}",
+            text(&colors.colors[4], "Documents/"),
+            text(&colors.colors[6], "link ->"),
+            text(&colors.colors[2], "script.sh"),
+            text(&colors.colors[5], "photo.png")
+        ),
+        format!(
+            "{}{}",
+            text(&colors.colors[1], "error"),
+            text(&colors.foreground, ": mismatched types")
+        ),
+        format!(
+            "{}{}",
+            text(&colors.colors[3], "warning"),
+            text(&colors.foreground, ": unused variable")
+        ),
+        text(&colors.colors[8], "// a comment in bright black"),
+    ];
+    for line in sample.iter() {
+        output.push_str(&format!("{}  {}  \x1b[0m\n", background, line));
+    }
+    output
+}
+
+#[test]
+fn preview_test() {
+    assert_eq!(ansi256(&Color::from_hex("#ff0000")), 196);
+    assert_eq!(ansi256(&Color::from_hex("#808080")), 244);
+    assert_eq!(ansi256(&Color::from_hex("#000000")), 16);
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    assert!(render(&nord, true).contains("\x1b[48;2;46;52;64m"));
+    assert!(render(&nord, false).contains("\x1b[48;5;"));
+    assert!(render(&nord, false).contains("#bf616a"));
+}
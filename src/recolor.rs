@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+const RECOLORED_FILE: &str = "recolored-wallpaper.png";
+
+/// how the pixels of an image are mapped to the palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// the nearest palette color in OKLab
+    Nearest,
+    /// the nearest color with Floyd-Steinberg error diffusion
+    Dither,
+    /// the hue and chroma of the nearest color, keeping the lightness of the pixel
+    Tint,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Mode::Nearest),
+            "dither" => Some(Mode::Dither),
+            "tint" => Some(Mode::Tint),
+            _ => None,
+        }
+    }
+}
+
+/// the palette in OKLab, without duplicates
+fn palette(colors: &Colors) -> Vec<(f64, f64, f64)> {
+    let mut slots = vec![colors.background, colors.foreground];
+    slots.extend(colors.colors.iter());
+    slots.sort();
+    slots.dedup();
+    slots.iter().map(colorspace::to_oklab).collect()
+}
+
+fn nearest(palette: &[(f64, f64, f64)], (l, a, b): (f64, f64, f64)) -> (f64, f64, f64) {
+    let distance = |p: &(f64, f64, f64)| (p.0 - l).powi(2) + (p.1 - a).powi(2) + (p.2 - b).powi(2);
+    *palette
+        .iter()
+        .min_by(|x, y| distance(x).total_cmp(&distance(y)))
+        .unwrap_or(&(l, a, b))
+}
+
+fn to_rgb((l, a, b): (f64, f64, f64)) -> [u8; 3] {
+    let (r, g, b) = colorspace::oklab_to_linear(l, a, b);
+    [
+        colorspace::from_linear(r),
+        colorspace::from_linear(g),
+        colorspace::from_linear(b),
+    ]
+}
+
+/// add a share of the quantization error to a neighbouring pixel
+fn spread(cell: &mut (f64, f64, f64), error: (f64, f64, f64), weight: f64) {
+    cell.0 += error.0 * weight / 16.0;
+    cell.1 += error.1 * weight / 16.0;
+    cell.2 += error.2 * weight / 16.0;
+}
+
+/// map every pixel of `image` to the palette, keeping its alpha
+pub fn recolor(image: &RgbaImage, colors: &Colors, mode: Mode) -> RgbaImage {
+    let palette = palette(colors);
+    let linear = (0..=255u8).map(colorspace::to_linear).collect::<Vec<f64>>();
+    let oklab = |[r, g, b, _]: [u8; 4]| {
+        colorspace::linear_to_oklab(linear[r as usize], linear[g as usize], linear[b as usize])
+    };
+    let (width, height) = image.dimensions();
+    let mut output = RgbaImage::new(width, height);
+    match mode {
+        Mode::Nearest | Mode::Tint => {
+            // wallpapers repeat a lot of colors
+            let mut cache: HashMap<[u8; 3], [u8; 3]> = HashMap::new();
+            for (x, y, pixel) in image.enumerate_pixels() {
+                let Rgba([r, g, b, alpha]) = *pixel;
+                let [r, g, b] = *cache.entry([r, g, b]).or_insert_with(|| {
+                    let lab = oklab(pixel.0);
+                    let target = nearest(&palette, lab);
+                    match mode {
+                        Mode::Tint => to_rgb((lab.0, target.1, target.2)),
+                        _ => to_rgb(target),
+                    }
+                });
+                output.put_pixel(x, y, Rgba([r, g, b, alpha]));
+            }
+        }
+        Mode::Dither => {
+            // the error carried to the current and the next row
+            let mut current = vec![(0.0, 0.0, 0.0); width as usize + 2];
+            let mut next = vec![(0.0, 0.0, 0.0); width as usize + 2];
+            for y in 0..height {
+                for x in 0..width {
+                    let pixel = image.get_pixel(x, y);
+                    let i = x as usize + 1;
+                    let lab = oklab(pixel.0);
+                    let wanted = (
+                        lab.0 + current[i].0,
+                        lab.1 + current[i].1,
+                        lab.2 + current[i].2,
+                    );
+                    let chosen = nearest(&palette, wanted);
+                    let error = (
+                        wanted.0 - chosen.0,
+                        wanted.1 - chosen.1,
+                        wanted.2 - chosen.2,
+                    );
+                    spread(&mut current[i + 1], error, 7.0);
+                    spread(&mut next[i - 1], error, 3.0);
+                    spread(&mut next[i], error, 5.0);
+                    spread(&mut next[i + 1], error, 1.0);
+                    let [r, g, b] = to_rgb(chosen);
+                    output.put_pixel(x, y, Rgba([r, g, b, pixel.0[3]]));
+                }
+                current = std::mem::replace(&mut next, vec![(0.0, 0.0, 0.0); width as usize + 2]);
+            }
+        }
+    };
+    output
+}
+
+/// recolor the image at `path` and write it as `recolored-wallpaper.png` in the output directory
+pub fn recolor_file(
+    path: &Path,
+    colors: &Colors,
+    mode: Mode,
+    output_dir: &Path,
+) -> Result<PathBuf, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .to_rgba8();
+    let target = output_dir.join(RECOLORED_FILE);
+    recolor(&image, colors, mode)
+        .save(&target)
+        .map_err(|e| e.to_string())?;
+    Ok(target)
+}
+
+#[test]
+fn recolor_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let image = RgbaImage::from_fn(8, 8, |x, _| {
+        if x < 4 {
+            Rgba([250, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 0, 128])
+        }
+    });
+    let red = colorspace::rgb(&nord.colors[1]);
+    let nearest = recolor(&image, &nord, Mode::Nearest);
+    assert_eq!(nearest.get_pixel(0, 0), &Rgba([red.0, red.1, red.2, 255]));
+    // the darkest color of nord is its background, and alpha is kept
+    let background = colorspace::rgb(&nord.background);
+    assert_eq!(
+        nearest.get_pixel(7, 7),
+        &Rgba([background.0, background.1, background.2, 128])
+    );
+
+    let tinted = recolor(&image, &nord, Mode::Tint);
+    assert_eq!(tinted.get_pixel(7, 7), &Rgba([0, 0, 0, 128]));
+
+    let dithered = recolor(&image, &nord, Mode::Dither);
+    let palette = palette(&nord)
+        .into_iter()
+        .map(to_rgb)
+        .collect::<Vec<[u8; 3]>>();
+    for (_, _, pixel) in dithered.enumerate_pixels() {
+        assert!(palette.contains(&[pixel.0[0], pixel.0[1], pixel.0[2]]));
+    }
+}
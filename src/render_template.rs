@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::path::PathBuf;
 
 pub fn render_template(
     name: &str,
@@ -20,9 +21,44 @@ pub fn render_template(
     Ok(())
 }
 
+/// register every template once so that it can be rendered repeatedly
+/// templates that fail to compile are reported and skipped
+pub fn compile_templates(paths: &[PathBuf]) -> handlebars::Handlebars<'static> {
+    let mut handler = handlebars::Handlebars::new();
+    for path in paths {
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(n) => n.to_string(),
+            None => {
+                eprintln!("Unexpected file name: {:?}", path.file_name());
+                continue;
+            }
+        };
+        if let Err(e) = handler.register_template_file(&name, path) {
+            eprintln!("Could not compile {}: {}", name, e);
+        };
+    }
+    handler
+}
+
+/// render an already registered template into the output directory
+pub fn render_compiled(
+    handler: &handlebars::Handlebars,
+    name: &str,
+    output_dir: &Path,
+    data: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), handlebars::RenderError> {
+    if !output_dir.exists() {
+        eprintln!("{} does not exist", output_dir.display());
+        eprintln!("Creating {}", output_dir.display());
+        std::fs::create_dir_all(output_dir)?;
+    };
+    let file = std::fs::File::create(output_dir.join(name))?;
+    handler.render_to_write(name, &data, file)?;
+    Ok(())
+}
+
 #[test]
 fn lets_test() {
-    use std::path::PathBuf;
     let templates = [
         "colors",
         "colors.css",
@@ -83,15 +119,21 @@ fn lets_test() {
     foreground = "#cac0a9"
     cursor = "#cac0a9""##,
     );
-    let jason = config.to_json();
-    let mut data_opt: serde_json::Value = serde_json::from_str(&jason).unwrap();
-    let data = data_opt.as_object_mut().unwrap();
+    let mut data = config.template_data();
+    // the rofi templates read these
+    for prefix in &["dark_", "light_"] {
+        for name in &["background", "foreground", "color1", "color2", "color3"] {
+            let key = format!("{}{}", prefix, name);
+            let value = data.get(&key).and_then(|v| v.as_str()).unwrap_or("");
+            assert!(!value.is_empty(), "{} is empty", key);
+        }
+    }
     for item in templates {
         render_template(
             item,
             &PathBuf::from(&format!("assets/templates/{}", item)),
             &PathBuf::from("/tmp/templates"),
-            data,
+            &mut data,
         )
         .unwrap();
     }
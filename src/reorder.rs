@@ -0,0 +1,105 @@
+use colordata::traits::*;
+use text_colorizer::Colorize;
+
+use crate::colorspace;
+use crate::seed::hue_difference;
+use crate::seed::ACCENT_HUES;
+use crate::structures::Colors;
+
+/// chroma above which the hue of a color counts fully, grays barely care where they go
+const FULL_CHROMA: f64 = 0.1;
+
+/// the hue and chroma of the pair color{n} / color{n+8}, from the more colorful of the two
+fn pair_hue(colors: &Colors, index: usize) -> (f64, f64) {
+    let (_, base_chroma, base_hue) = colorspace::to_oklch(&colors.colors[index]);
+    let (_, bright_chroma, bright_hue) = colorspace::to_oklch(&colors.colors[index + 8]);
+    if bright_chroma > base_chroma {
+        (bright_hue, bright_chroma)
+    } else {
+        (base_hue, base_chroma)
+    }
+}
+
+/// the cheapest way to put the pairs in `remaining` into the slots from `slot` on
+fn best(costs: &[[f64; 6]; 6], slot: usize, remaining: &mut Vec<usize>) -> (f64, Vec<usize>) {
+    if slot == 6 {
+        return (0.0, Vec::new());
+    };
+    let mut cheapest = (f64::INFINITY, Vec::new());
+    for position in 0..remaining.len() {
+        let pair = remaining.remove(position);
+        let (cost, mut rest) = best(costs, slot + 1, remaining);
+        let cost = cost + costs[slot][pair];
+        if cost < cheapest.0 {
+            rest.insert(0, pair);
+            cheapest = (cost, rest);
+        };
+        remaining.insert(position, pair);
+    }
+    cheapest
+}
+
+/// for red, green, yellow, blue, magenta and cyan, the index (1 .. 6) of the pair that goes there
+/// the pairs are assigned together so the total hue distance is the smallest,
+/// weighted by chroma so that grays are placed last
+pub fn assignment(colors: &Colors) -> Vec<usize> {
+    let mut costs = [[0.0; 6]; 6];
+    for pair in 0..6 {
+        let (hue, chroma) = pair_hue(colors, pair + 1);
+        for (slot, &target) in ACCENT_HUES.iter().enumerate() {
+            costs[slot][pair] = hue_difference(hue, target).abs() * chroma.min(FULL_CHROMA);
+        }
+    }
+    best(&costs, 0, &mut (0..6).collect())
+        .1
+        .iter()
+        .map(|pair| pair + 1)
+        .collect()
+}
+
+/// the palette with color1 .. color6 (and color9 .. color14 along with them) in their ansi slots
+/// background, foreground, cursor and the grays stay where they are
+pub fn reorder(colors: &Colors) -> Colors {
+    let mut reordered = *colors;
+    for (slot, from) in assignment(colors).into_iter().enumerate() {
+        reordered.colors[slot + 1] = colors.colors[from];
+        reordered.colors[slot + 9] = colors.colors[from + 8];
+    }
+    reordered
+}
+
+pub fn print_report(colors: &Colors) {
+    for (slot, from) in assignment(colors).into_iter().enumerate() {
+        if from != slot + 1 {
+            println!(
+                "{}",
+                format!(
+                    "reordered: color{} is now color{} ({})",
+                    from,
+                    slot + 1,
+                    colors.colors[from].hex()
+                )
+                .yellow()
+            );
+        };
+    }
+}
+
+#[test]
+fn reorder_test() {
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    assert_eq!(assignment(&nord), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(reorder(&nord), nord);
+
+    // blue in red's place and the other way around
+    let mut swapped = nord;
+    swapped.colors.swap(1, 4);
+    swapped.colors.swap(9, 12);
+    let reordered = reorder(&swapped);
+    assert_eq!(reordered, nord);
+    assert_eq!(reordered.background, swapped.background);
+    assert_eq!(reordered.foreground, swapped.foreground);
+}
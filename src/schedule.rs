@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+/// switch between a light and a dark theme by the time of day
+/// either at fixed local times (`light_from`, `dark_from` as "HH:MM")
+/// or at sunrise and sunset, computed from `latitude` and `longitude`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Schedule {
+    pub light_from: Option<String>,
+    pub dark_from: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub light: Option<String>,
+    pub dark: Option<String>,
+    pub light_wallpaper: Option<String>,
+    pub dark_wallpaper: Option<String>,
+}
+
+const PERIOD_FILE: &str = "schedule_period";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Light,
+    Dark,
+}
+
+impl Period {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Light => "light",
+            Period::Dark => "dark",
+        }
+    }
+}
+
+impl Schedule {
+    pub fn from_toml(value: &toml::Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let number = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+        };
+        Schedule {
+            light_from: string("light_from"),
+            dark_from: string("dark_from"),
+            latitude: number("latitude"),
+            longitude: number("longitude"),
+            light: string("light"),
+            dark: string("dark"),
+            light_wallpaper: string("light_wallpaper"),
+            dark_wallpaper: string("dark_wallpaper"),
+        }
+    }
+
+    /// the period at `now` (seconds since the unix epoch) with the local time
+    /// `offset` seconds ahead of UTC; None when the schedule has no times
+    pub fn period_at(&self, now: i64, offset: i64) -> Option<Period> {
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            let day = (now + offset).div_euclid(86400);
+            return Some(match sun_times(day, latitude, longitude) {
+                Some((sunrise, sunset)) if now >= sunrise && now < sunset => Period::Light,
+                Some(_) => Period::Dark,
+                // polar day or polar night
+                None if is_polar_day(day, latitude, longitude) => Period::Light,
+                None => Period::Dark,
+            });
+        };
+        let light_from = parse_time(self.light_from.as_deref()?)?;
+        let dark_from = parse_time(self.dark_from.as_deref()?)?;
+        let minute = (now + offset).rem_euclid(86400) / 60;
+        let light = if light_from <= dark_from {
+            minute >= light_from && minute < dark_from
+        } else {
+            minute >= light_from || minute < dark_from
+        };
+        Some(if light { Period::Light } else { Period::Dark })
+    }
+
+    pub fn input_for(&self, period: Period) -> Option<&String> {
+        match period {
+            Period::Light => self.light.as_ref(),
+            Period::Dark => self.dark.as_ref(),
+        }
+    }
+
+    pub fn wallpaper_for(&self, period: Period) -> Option<&String> {
+        match period {
+            Period::Light => self.light_wallpaper.as_ref(),
+            Period::Dark => self.dark_wallpaper.as_ref(),
+        }
+    }
+
+    /// the period right now, in the local time zone
+    pub fn current_period(&self) -> Option<Period> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        self.period_at(now, local_offset())
+    }
+}
+
+/// the period that was applied last, as saved in the output directory
+pub fn last_period(output_dir: &Path) -> Option<Period> {
+    match std::fs::read_to_string(output_dir.join(PERIOD_FILE))
+        .ok()?
+        .trim()
+    {
+        "light" => Some(Period::Light),
+        "dark" => Some(Period::Dark),
+        _ => None,
+    }
+}
+
+pub fn save_period(period: Period, output_dir: &Path) {
+    if let Err(e) = std::fs::write(output_dir.join(PERIOD_FILE), period.as_str()) {
+        eprintln!("{}", format!("Could not save schedule period: {}", e).red());
+    };
+}
+
+/// "HH:MM" to minutes after midnight
+fn parse_time(time: &str) -> Option<i64> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) {
+        eprintln!("{}", format!("Invalid time in schedule: {}", time).red());
+        return None;
+    };
+    Some(hours * 60 + minutes)
+}
+
+/// the offset of the local time zone from UTC in seconds, as reported by `date +%z`
+pub fn local_offset() -> i64 {
+    let output = std::process::Command::new("date").arg("+%z").output();
+    let zone = match output {
+        Ok(o) => String::from_utf8_lossy(&o.stdout).trim().to_string(),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not read the time zone, using UTC: {}", e).yellow()
+            );
+            return 0;
+        }
+    };
+    // +HHMM or -HHMM
+    let sign = if zone.starts_with('-') { -1 } else { 1 };
+    let digits = zone.trim_start_matches(|c| c == '+' || c == '-');
+    match (digits.get(0..2), digits.get(2..4)) {
+        (Some(h), Some(m)) => match (h.parse::<i64>(), m.parse::<i64>()) {
+            (Ok(h), Ok(m)) => sign * (h * 3600 + m * 60),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// sunrise and sunset on `day` (days since the unix epoch) as seconds since the epoch
+/// None when the sun does not rise or does not set that day
+/// see https://en.wikipedia.org/wiki/Sunrise_equation
+pub fn sun_times(day: i64, latitude: f64, longitude: f64) -> Option<(i64, i64)> {
+    let (transit, cos_hour_angle) = solar_transit(day, latitude, longitude);
+    if cos_hour_angle.abs() > 1.0 {
+        return None;
+    };
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    let to_unix = |julian: f64| ((julian - 2440587.5) * 86400.0).round() as i64;
+    Some((
+        to_unix(transit - hour_angle / 360.0),
+        to_unix(transit + hour_angle / 360.0),
+    ))
+}
+
+/// whether the sun stays above the horizon all day
+fn is_polar_day(day: i64, latitude: f64, longitude: f64) -> bool {
+    solar_transit(day, latitude, longitude).1 < -1.0
+}
+
+/// the julian date of solar noon and the cosine of the hour angle of sunrise
+/// the cosine is below -1 when the sun never sets and above 1 when it never rises
+fn solar_transit(day: i64, latitude: f64, longitude: f64) -> (f64, f64) {
+    let n = (day as f64 + 2440587.5 - 2451545.0 + 0.0008).ceil();
+    let mean_solar_time = n - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = 2451545.0 + mean_solar_time + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic).sin();
+    let declination = (ecliptic.sin() * 23.4397f64.to_radians().sin()).asin();
+    let phi = latitude.to_radians();
+    let cos_hour_angle = ((-0.833f64).to_radians().sin() - phi.sin() * declination.sin())
+        / (phi.cos() * declination.cos());
+    (transit, cos_hour_angle)
+}
+
+#[test]
+fn schedule_test() {
+    // 2024-06-21 in Berlin: sunrise 02:43 UTC, sunset 19:33 UTC
+    let day = 19895;
+    let (sunrise, sunset) = sun_times(day, 52.52, 13.405).unwrap();
+    assert!((sunrise - (day * 86400 + 2 * 3600 + 43 * 60)).abs() < 300);
+    assert!((sunset - (day * 86400 + 19 * 3600 + 33 * 60)).abs() < 300);
+    assert!(sun_times(day, 80.0, 0.0).is_none());
+
+    let schedule = Schedule {
+        light_from: Some("07:00".to_string()),
+        dark_from: Some("19:30".to_string()),
+        ..Schedule::default()
+    };
+    assert_eq!(schedule.period_at(12 * 3600, 0), Some(Period::Light));
+    assert_eq!(schedule.period_at(12 * 3600, 8 * 3600), Some(Period::Dark));
+    assert_eq!(schedule.period_at(6 * 3600, 0), Some(Period::Dark));
+}
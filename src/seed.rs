@@ -0,0 +1,160 @@
+use colordata::Color;
+
+use crate::colorspace;
+use crate::light;
+use crate::structures::Colors;
+
+/// the OKLCH hues of red, green, yellow, blue, magenta and cyan (color1 .. color6)
+pub const ACCENT_HUES: [f64; 6] = [25.0, 140.0, 90.0, 255.0, 330.0, 195.0];
+/// how far an accent hue moves towards the harmony, as a share and at most in degrees
+const HUE_PULL: f64 = 0.25;
+const MAX_HUE_SHIFT: f64 = 15.0;
+
+/// which hues the accents lean towards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Harmony {
+    /// the seed hue
+    Analogous,
+    /// the seed hue and its opposite
+    Complementary,
+    /// the seed hue and the hues a third of the circle away
+    Triadic,
+}
+
+impl Harmony {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "analogous" => Some(Harmony::Analogous),
+            "complementary" => Some(Harmony::Complementary),
+            "triadic" => Some(Harmony::Triadic),
+            _ => None,
+        }
+    }
+
+    fn anchors(&self, hue: f64) -> Vec<f64> {
+        match self {
+            Harmony::Analogous => vec![hue],
+            Harmony::Complementary => vec![hue, hue + 180.0],
+            Harmony::Triadic => vec![hue, hue + 120.0, hue + 240.0],
+        }
+    }
+}
+
+/// the signed difference from `from` to `to` along the shorter way around the circle
+pub fn hue_difference(from: f64, to: f64) -> f64 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+/// `seed:#5e81ac`, optionally followed by `:analogous`, `:complementary` or `:triadic`
+/// and `:dark` or `:light`, eg. `seed:#5e81ac:triadic:light`
+pub fn parse(input: &str) -> Result<(Color, Harmony, bool), String> {
+    let mut parts = input.split(':');
+    let hex = parts.next().unwrap_or("");
+    let digits = hex.trim_start_matches('#');
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid seed color: {}", hex));
+    };
+    let (mut harmony, mut light) = (Harmony::Analogous, false);
+    for part in parts {
+        match part {
+            "dark" => light = false,
+            "light" => light = true,
+            name => match Harmony::from_name(name) {
+                Some(h) => harmony = h,
+                None => return Err(format!("unknown seed option: {}", name)),
+            },
+        };
+    }
+    Ok((Color::from_hex(&format!("#{}", digits)), harmony, light))
+}
+
+/// a full palette around one color
+/// background, foreground and the grays take a hint of the seed hue,
+/// the accents keep their ansi meaning and lean towards the harmony hues,
+/// the accent closest in hue to the seed takes its hue and chroma
+pub fn generate(seed: &Color, harmony: Harmony, light: bool) -> Colors {
+    let (_, seed_chroma, seed_hue) = colorspace::to_oklch(seed);
+    let gray = |lightness: f64, chroma: f64| {
+        colorspace::from_oklch(lightness, seed_chroma.min(chroma), seed_hue, seed)
+    };
+    // lightness of background, foreground, color0, color7, color8, color15
+    let (bg, fg, black, white, bright_black, bright_white) = if light {
+        (0.97, 0.30, 0.93, 0.40, 0.60, 0.25)
+    } else {
+        (0.20, 0.90, 0.26, 0.82, 0.55, 0.95)
+    };
+    let mut colors = Colors {
+        background: gray(bg, 0.02),
+        foreground: gray(fg, 0.015),
+        cursor: *seed,
+        colors: [Color::default(); 16],
+    };
+    colors.colors[0] = gray(black, 0.02);
+    colors.colors[7] = gray(white, 0.015);
+    colors.colors[8] = gray(bright_black, 0.02);
+    colors.colors[15] = gray(bright_white, 0.01);
+
+    let anchors = harmony.anchors(seed_hue);
+    let closest = (0..6)
+        .min_by(|&a, &b| {
+            hue_difference(ACCENT_HUES[a], seed_hue)
+                .abs()
+                .total_cmp(&hue_difference(ACCENT_HUES[b], seed_hue).abs())
+        })
+        .unwrap_or(3);
+    let chroma = seed_chroma.clamp(0.1, 0.16);
+    for (index, &target) in ACCENT_HUES.iter().enumerate() {
+        let (hue, chroma) = if index == closest {
+            (seed_hue, seed_chroma.max(0.04))
+        } else {
+            let pull = anchors
+                .iter()
+                .map(|&anchor| hue_difference(target, anchor))
+                .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0);
+            (
+                target + (pull * HUE_PULL).clamp(-MAX_HUE_SHIFT, MAX_HUE_SHIFT),
+                chroma,
+            )
+        };
+        let (base, bright) = if light {
+            let base = colorspace::from_oklch(0.55, chroma, hue, seed);
+            let bright = colorspace::from_oklch(0.50, chroma * 1.1, hue, seed);
+            (
+                light::darken_until_readable(&base, &colors.background, 0.55, 3.0),
+                light::darken_until_readable(&bright, &colors.background, 0.50, 3.0),
+            )
+        } else {
+            (
+                colorspace::from_oklch(0.72, chroma, hue, seed),
+                colorspace::from_oklch(0.80, chroma * 1.1, hue, seed),
+            )
+        };
+        colors.colors[index + 1] = base;
+        colors.colors[index + 9] = bright;
+    }
+    colors
+}
+
+#[test]
+fn seed_test() {
+    let (seed, harmony, light) = parse("#5e81ac:triadic:light").unwrap();
+    assert_eq!((harmony, light), (Harmony::Triadic, true));
+    assert!(parse("#5e81ac:sideways").is_err());
+    assert!(parse("blue").is_err());
+
+    for light in [false, true].iter() {
+        let colors = generate(&seed, Harmony::Analogous, *light);
+        assert_eq!(colorspace::is_dark(&colors), !light);
+        for index in (1..=6).chain(9..=14) {
+            assert!(colorspace::contrast_ratio(&colors.colors[index], &colors.background) >= 3.0);
+        }
+        // red stays red
+        let (_, _, hue) = colorspace::to_oklch(&colors.colors[1]);
+        assert!(hue_difference(hue, ACCENT_HUES[0]).abs() <= MAX_HUE_SHIFT + 1.0);
+        // the seed is blue, so color4 takes its hue
+        let (_, _, hue) = colorspace::to_oklch(&colors.colors[4]);
+        let (_, _, seed_hue) = colorspace::to_oklch(&seed);
+        assert!(hue_difference(hue, seed_hue).abs() < 2.0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use colordata::traits::*;
+use colordata::Color;
+use text_colorizer::Colorize;
+
+use crate::structures::Colors;
+
+/// build the OSC escape sequences that recolor a running terminal
+/// 4 - the 16 ansi colors and the color16 .. color255 set in the input, 10 - foreground,
+/// 11 - background, 12 - cursor, 708 - border/background in urxvt
+pub fn build_sequences(colors: &Colors, extended: Option<&BTreeMap<u8, Color>>) -> String {
+    let mut sequences = String::new();
+    for (index, color) in colors.colors.iter().enumerate() {
+        sequences.push_str(&format!("\x1b]4;{};{}\x1b\\", index, color.hex()));
+    }
+    for (index, color) in extended.into_iter().flatten() {
+        sequences.push_str(&format!("\x1b]4;{};{}\x1b\\", index, color.hex()));
+    }
+    sequences.push_str(&format!("\x1b]10;{}\x1b\\", colors.foreground.hex()));
+    sequences.push_str(&format!("\x1b]11;{}\x1b\\", colors.background.hex()));
+    sequences.push_str(&format!("\x1b]12;{}\x1b\\", colors.cursor.hex()));
+    sequences.push_str(&format!("\x1b]708;{}\x1b\\", colors.background.hex()));
+    sequences
+}
+
+/// save the sequences to `sequences` in the output directory
+/// so that new shells can `cat` it
+pub fn save_sequences(sequences: &str, output_dir: &Path) -> std::io::Result<()> {
+    std::fs::write(output_dir.join("sequences"), sequences)
+}
+
+/// write the sequences to every pseudo-terminal under /dev/pts owned by the current user
+pub fn send_sequences(sequences: &str) {
+    // /proc/self is owned by the uid of the running process
+    let uid = match std::fs::metadata("/proc/self") {
+        Ok(meta) => meta.uid(),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not determine current user: {}", e).yellow()
+            );
+            return;
+        }
+    };
+    let terminals = match std::fs::read_dir("/dev/pts") {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", format!("Could not read /dev/pts: {}", e).yellow());
+            return;
+        }
+    };
+    for terminal in terminals.flatten() {
+        let name = terminal.file_name();
+        let is_pty = name
+            .to_str()
+            .map(|n| n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_pty {
+            continue;
+        };
+        match terminal.metadata() {
+            Ok(meta) if meta.uid() == uid => (),
+            _ => continue,
+        };
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .open(terminal.path())
+        {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        if let Err(e) = file.write_all(sequences.as_bytes()) {
+            eprintln!(
+                "{}",
+                format!("Could not write to {}: {}", terminal.path().display(), e).yellow()
+            );
+        };
+    }
+}
+
+#[test]
+fn sequences_test() {
+    let colors = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let sequences = build_sequences(&colors, None);
+    assert!(sequences.starts_with("\x1b]4;0;#3b4252\x1b\\"));
+    assert!(sequences.contains("\x1b]11;#2e3440\x1b\\"));
+    assert!(sequences.ends_with("\x1b]708;#2e3440\x1b\\"));
+
+    let mut extended = BTreeMap::new();
+    extended.insert(17, Color::from_hex("#123456"));
+    let sequences = build_sequences(&colors, Some(&extended));
+    assert!(sequences.contains("\x1b]4;15;#eceff4\x1b\\\x1b]4;17;#123456\x1b\\"));
+}
@@ -0,0 +1,268 @@
+use std::path::Path;
+
+use colordata::traits::*;
+use colordata::Color;
+use image::Rgba;
+use image::RgbaImage;
+
+use crate::colorspace;
+use crate::structures::Colors;
+
+const MARGIN: u32 = 24;
+const GAP: u32 = 16;
+const SWATCH_WIDTH: u32 = 112;
+const SWATCH_HEIGHT: u32 = 72;
+const LABEL_HEIGHT: u32 = 28;
+const COLUMNS: u32 = 8;
+/// the bitmap font is scaled up by this in png sheets
+const FONT_SCALE: u32 = 2;
+
+/// a 5x7 bitmap font with just what hex values need, one row per byte
+const GLYPHS: [(char, [u8; 7]); 17] = [
+    ('0', [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e]),
+    ('1', [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e]),
+    ('2', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f]),
+    ('3', [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e]),
+    ('4', [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02]),
+    ('5', [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e]),
+    ('6', [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e]),
+    ('7', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e]),
+    ('9', [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c]),
+    ('a', [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f]),
+    ('b', [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e]),
+    ('c', [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e]),
+    ('d', [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f]),
+    ('e', [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e]),
+    ('f', [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08]),
+    ('#', [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a]),
+];
+
+/// a swatch on the sheet, at the top left corner `x`, `y`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+    pub x: u32,
+    pub y: u32,
+    pub color: Color,
+}
+
+/// background, foreground and cursor in the first row, then the normal and bright colors
+pub fn layout(colors: &Colors) -> Vec<Swatch> {
+    let rows = [
+        vec![colors.background, colors.foreground, colors.cursor],
+        colors.colors[..8].to_vec(),
+        colors.colors[8..].to_vec(),
+    ];
+    let mut swatches = Vec::new();
+    for (row, row_colors) in rows.iter().enumerate() {
+        for (column, color) in row_colors.iter().enumerate() {
+            swatches.push(Swatch {
+                x: MARGIN + column as u32 * (SWATCH_WIDTH + GAP),
+                y: MARGIN + row as u32 * (SWATCH_HEIGHT + LABEL_HEIGHT + GAP),
+                color: *color,
+            });
+        }
+    }
+    swatches
+}
+
+/// the size of the sheet without a thumbnail
+fn sheet_size() -> (u32, u32) {
+    (
+        2 * MARGIN + COLUMNS * SWATCH_WIDTH + (COLUMNS - 1) * GAP,
+        2 * MARGIN + 3 * (SWATCH_HEIGHT + LABEL_HEIGHT) + 2 * GAP,
+    )
+}
+
+/// the wallpaper scaled down to the height of the swatches
+pub fn thumbnail(path: &Path) -> Result<RgbaImage, String> {
+    let (_, height) = sheet_size();
+    let image = image::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(image.thumbnail(u32::MAX, height - 2 * MARGIN).to_rgba8())
+}
+
+fn rgba(color: &Color) -> Rgba<u8> {
+    let (r, g, b) = colorspace::rgb(color);
+    Rgba([r, g, b, 255])
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn draw_text(image: &mut RgbaImage, x: u32, y: u32, text: &str, color: Rgba<u8>) {
+    for (index, character) in text.chars().enumerate() {
+        let glyph = match GLYPHS.iter().find(|(c, _)| *c == character) {
+            Some((_, rows)) => rows,
+            None => continue,
+        };
+        let left = x + index as u32 * 6 * FONT_SCALE;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..5 {
+                if bits & (0x10 >> column) != 0 {
+                    fill(
+                        image,
+                        left + column * FONT_SCALE,
+                        y + row as u32 * FONT_SCALE,
+                        FONT_SCALE,
+                        FONT_SCALE,
+                        color,
+                    );
+                };
+            }
+        }
+    }
+}
+
+/// the sheet as an image, with the thumbnail on the right
+pub fn render_png(colors: &Colors, thumbnail: Option<&RgbaImage>) -> RgbaImage {
+    let (width, height) = sheet_size();
+    let extra = thumbnail.map_or(0, |t| t.width() + MARGIN);
+    let mut image = RgbaImage::new(width + extra, height);
+    fill(
+        &mut image,
+        0,
+        0,
+        width + extra,
+        height,
+        rgba(&colors.background),
+    );
+    for swatch in layout(colors) {
+        fill(
+            &mut image,
+            swatch.x,
+            swatch.y,
+            SWATCH_WIDTH,
+            SWATCH_HEIGHT,
+            rgba(&swatch.color),
+        );
+        draw_text(
+            &mut image,
+            swatch.x,
+            swatch.y + SWATCH_HEIGHT + (LABEL_HEIGHT - 7 * FONT_SCALE) / 2,
+            &swatch.color.hex(),
+            rgba(&colors.foreground),
+        );
+    }
+    if let Some(thumb) = thumbnail {
+        for (x, y, pixel) in thumb.enumerate_pixels() {
+            image.put_pixel(width + x, MARGIN + y, *pixel);
+        }
+    };
+    image
+}
+
+/// the sheet as an svg document, the thumbnail embedded as a png
+pub fn render_svg(colors: &Colors, thumbnail: Option<&RgbaImage>) -> Result<String, String> {
+    let (width, height) = sheet_size();
+    let extra = thumbnail.map_or(0, |t| t.width() + MARGIN);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width + extra,
+        h = height
+    );
+    svg.push_str(&format!(
+        "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        colors.background.hex()
+    ));
+    for swatch in layout(colors) {
+        svg.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"{}\"/>\n",
+            swatch.x,
+            swatch.y,
+            SWATCH_WIDTH,
+            SWATCH_HEIGHT,
+            swatch.color.hex()
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"16\" fill=\"{}\">{}</text>\n",
+            swatch.x,
+            swatch.y + SWATCH_HEIGHT + LABEL_HEIGHT - 8,
+            colors.foreground.hex(),
+            swatch.color.hex()
+        ));
+    }
+    if let Some(thumb) = thumbnail {
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(thumb.clone())
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .map_err(|e| e.to_string())?;
+        svg.push_str(&format!(
+            "  <image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" href=\"data:image/png;base64,{}\"/>\n",
+            width,
+            MARGIN,
+            thumb.width(),
+            thumb.height(),
+            base64(&png)
+        ));
+    };
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for position in 0..4 {
+            if position <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * position)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            };
+        }
+    }
+    encoded
+}
+
+/// write the sheet to `path`, as svg or png by its extension
+pub fn write_sheet(colors: &Colors, wallpaper: Option<&Path>, path: &Path) -> Result<(), String> {
+    let thumbnail = match wallpaper {
+        Some(wal) => Some(thumbnail(wal)?),
+        None => None,
+    };
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => {
+            std::fs::write(path, render_svg(colors, thumbnail.as_ref())?).map_err(|e| e.to_string())
+        }
+        Some("png") => render_png(colors, thumbnail.as_ref())
+            .save(path)
+            .map_err(|e| e.to_string()),
+        _ => Err(format!(
+            "{}: only .svg and .png are supported",
+            path.display()
+        )),
+    }
+}
+
+#[test]
+fn sheet_test() {
+    assert_eq!(base64(b"Man"), "TWFu");
+    assert_eq!(base64(b"Ma"), "TWE=");
+    assert_eq!(base64(b"M"), "TQ==");
+
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    assert_eq!(layout(&nord).len(), 19);
+    let svg = render_svg(&nord, None).unwrap();
+    assert!(svg.contains("fill=\"#bf616a\""));
+    assert!(svg.contains(">#bf616a</text>"));
+    let png = render_png(&nord, None);
+    let first = layout(&nord)[3];
+    assert_eq!(
+        png.get_pixel(first.x + 1, first.y + 1),
+        &rgba(&nord.colors[0])
+    );
+}
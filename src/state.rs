@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::config::Config;
+
+const STATE_FILE: &str = "state.json";
+
+/// everything needed to re-apply the last theme without reading the inputs again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct State {
+    pub config: Config,
+    pub no_sequences: bool,
+}
+
+/// save the resolved config as `state.json` in the output directory
+pub fn save_state(config: &Config, no_sequences: bool, output_dir: &Path) {
+    let state = State {
+        config: config.clone(),
+        no_sequences,
+    };
+    let json = serde_json::to_string_pretty(&state).unwrap();
+    match std::fs::write(output_dir.join(STATE_FILE), json) {
+        Ok(_) => println!("{}", format!("saved: {}", STATE_FILE).green()),
+        Err(e) => eprintln!("{}", format!("Could not save state: {}", e).red()),
+    };
+}
+
+/// load the state saved by the last run, if any
+pub fn load_state(output_dir: &Path) -> Option<State> {
+    let path = output_dir.join(STATE_FILE);
+    let json = match std::fs::read_to_string(&path) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not read {}: {}", path.display(), e).red()
+            );
+            return None;
+        }
+    };
+    match serde_json::from_str(&json) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not parse {}: {}", path.display(), e).red()
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::utils;
+
+/// programs that colstract knows how to drive to set the wallpaper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Feh,
+    Xwallpaper,
+    Nitrogen,
+    Swaybg,
+    Swww,
+    Hyprpaper,
+    Gnome,
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "feh" => Some(Backend::Feh),
+            "xwallpaper" => Some(Backend::Xwallpaper),
+            "nitrogen" => Some(Backend::Nitrogen),
+            "swaybg" => Some(Backend::Swaybg),
+            "swww" => Some(Backend::Swww),
+            "hyprpaper" => Some(Backend::Hyprpaper),
+            "gnome" => Some(Backend::Gnome),
+            _ => None,
+        }
+    }
+
+    /// the binary that has to be on $PATH for the backend to work
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Backend::Feh => "feh",
+            Backend::Xwallpaper => "xwallpaper",
+            Backend::Nitrogen => "nitrogen",
+            Backend::Swaybg => "swaybg",
+            Backend::Swww => "swww",
+            Backend::Hyprpaper => "hyprctl",
+            Backend::Gnome => "gsettings",
+        }
+    }
+
+    /// swaybg keeps running to display the image, so it is spawned and not waited on
+    pub fn is_daemon(&self) -> bool {
+        matches!(self, Backend::Swaybg)
+    }
+
+    /// pick a backend from the session type, the desktop and the binaries on $PATH
+    pub fn detect() -> Option<Self> {
+        let desktop = std::env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .to_lowercase();
+        let wayland = std::env::var("WAYLAND_DISPLAY").is_ok();
+        let x11 = std::env::var("DISPLAY").is_ok();
+
+        let candidates: &[Backend] = if desktop.contains("gnome") {
+            &[Backend::Gnome]
+        } else if wayland && desktop.contains("hyprland") {
+            &[Backend::Hyprpaper, Backend::Swww, Backend::Swaybg]
+        } else if wayland {
+            &[Backend::Swww, Backend::Swaybg]
+        } else if x11 {
+            &[Backend::Feh, Backend::Xwallpaper, Backend::Nitrogen]
+        } else {
+            &[]
+        };
+        candidates
+            .iter()
+            .copied()
+            .find(|backend| backend.is_available())
+    }
+
+    /// the binary is on $PATH, and for hyprpaper its daemon is running,
+    /// hyprctl alone is there on any Hyprland setup
+    pub fn is_available(&self) -> bool {
+        if utils::find_in_path(self.binary()).is_none() {
+            return false;
+        };
+        match self {
+            Backend::Hyprpaper => process_running("hyprpaper"),
+            _ => true,
+        }
+    }
+
+    /// the commands to run, in order, to display `path` filling every output
+    pub fn commands(&self, path: &str) -> Vec<Vec<String>> {
+        let command = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        match self {
+            Backend::Feh => vec![command(&["feh", "--no-fehbg", "--bg-fill", path])],
+            Backend::Xwallpaper => vec![command(&["xwallpaper", "--zoom", path])],
+            Backend::Nitrogen => vec![command(&["nitrogen", "--set-zoom-fill", "--head=-1", path])],
+            Backend::Swaybg => vec![
+                command(&["pkill", "-x", "swaybg"]),
+                command(&["swaybg", "--output", "*", "--mode", "fill", "--image", path]),
+            ],
+            Backend::Swww => vec![command(&["swww", "img", path])],
+            Backend::Hyprpaper => vec![
+                command(&["hyprctl", "hyprpaper", "preload", path]),
+                command(&[
+                    "hyprctl",
+                    "hyprpaper",
+                    "wallpaper",
+                    format!(",{}", path).as_str(),
+                ]),
+            ],
+            Backend::Gnome => {
+                // gsettings wants an absolute uri
+                let absolute = std::fs::canonicalize(path)
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|_| path.to_string());
+                let uri = format!("file://{}", absolute);
+                vec![
+                    command(&[
+                        "gsettings",
+                        "set",
+                        "org.gnome.desktop.background",
+                        "picture-uri",
+                        uri.as_str(),
+                    ]),
+                    command(&[
+                        "gsettings",
+                        "set",
+                        "org.gnome.desktop.background",
+                        "picture-uri-dark",
+                        uri.as_str(),
+                    ]),
+                ]
+            }
+        }
+    }
+}
+
+/// whether a process with this name runs, from /proc/<pid>/comm
+fn process_running(name: &str) -> bool {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(val) => val,
+        Err(_) => return false,
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim() == name)
+            .unwrap_or(false)
+    })
+}
+
+#[test]
+fn backend_test() {
+    assert_eq!(Backend::from_name("SwayBG"), Some(Backend::Swaybg));
+    assert_eq!(Backend::from_name("unknown"), None);
+    let commands = Backend::Feh.commands("/home/user/Pictures/wall.png");
+    assert_eq!(
+        commands,
+        vec![vec![
+            "feh",
+            "--no-fehbg",
+            "--bg-fill",
+            "/home/user/Pictures/wall.png"
+        ]]
+    );
+}
@@ -11,3 +11,23 @@ pub struct Colors {
     pub cursor: Color,
     pub colors: [Color; 16],
 }
+
+impl Colors {
+    /// a color by its name in templates: background, foreground, cursor or color0 .. color15
+    pub fn slot(&self, name: &str) -> Option<Color> {
+        let mut colors = *self;
+        colors.slot_mut(name).copied()
+    }
+
+    pub fn slot_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "background" => Some(&mut self.background),
+            "foreground" => Some(&mut self.foreground),
+            "cursor" => Some(&mut self.cursor),
+            _ => {
+                let index = name.strip_prefix("color")?.parse::<usize>().ok()?;
+                self.colors.get_mut(index)
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
+pub mod backend;
 pub mod colors;
+pub mod selection;
 pub mod wallpaper;
 
+pub use backend::Backend;
 pub use colors::Colors;
+pub use selection::Selection;
 pub use wallpaper::Wallpaper;
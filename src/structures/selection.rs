@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use text_colorizer::Colorize;
+
+use crate::utils;
+
+/// the formats the image crate is built with, so that picked wallpapers can be recolored
+pub const DEFAULT_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+const CACHE_FILE: &str = "wallpaper_selection.json";
+const HISTORY_LIMIT: usize = 1024;
+
+/// how an image is picked when the wallpaper path is a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Random,
+    Sequential,
+    LeastRecentlyUsed,
+}
+
+impl Selection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "random" => Some(Selection::Random),
+            "sequential" => Some(Selection::Sequential),
+            "lru" | "least-recently-used" => Some(Selection::LeastRecentlyUsed),
+            _ => None,
+        }
+    }
+}
+
+/// what has been picked before in one directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectionCache {
+    pub index: Option<usize>,
+    pub history: Vec<String>,
+}
+
+/// the caches of every directory, by canonical path, kept in the output directory
+fn load_all(cache_dir: &Path) -> BTreeMap<String, SelectionCache> {
+    std::fs::read_to_string(cache_dir.join(CACHE_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn directory_key(dir: &Path) -> String {
+    std::fs::canonicalize(dir)
+        .unwrap_or_else(|_| dir.to_path_buf())
+        .display()
+        .to_string()
+}
+
+impl SelectionCache {
+    /// the picks made before in `dir`
+    pub fn load(cache_dir: &Path, dir: &Path) -> Self {
+        load_all(cache_dir)
+            .remove(&directory_key(dir))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, cache_dir: &Path, dir: &Path) {
+        let mut all = load_all(cache_dir);
+        all.insert(directory_key(dir), self.clone());
+        let json = serde_json::to_string_pretty(&all).unwrap();
+        if let Err(e) = std::fs::write(cache_dir.join(CACHE_FILE), json) {
+            eprintln!(
+                "{}",
+                format!("Could not save wallpaper selection: {}", e).yellow()
+            );
+        };
+    }
+
+    /// the wallpaper that was picked last
+    pub fn current(&self) -> Option<&String> {
+        self.history.last()
+    }
+
+    pub fn record(&mut self, index: usize, path: &str) {
+        self.index = Some(index);
+        self.history.retain(|p| p != path);
+        self.history.push(path.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            let excess = self.history.len() - HISTORY_LIMIT;
+            self.history.drain(..excess);
+        };
+    }
+}
+
+/// collect the images in `dir` with one of the `extensions`, sorted by path
+pub fn collect_images(dir: &Path, extensions: &[String], recursive: bool) -> Vec<PathBuf> {
+    let mut images = Vec::new();
+    collect_into(dir, extensions, recursive, &mut HashSet::new(), &mut images);
+    images.sort();
+    images
+}
+
+/// `visited` holds the canonical directories already read, so symlink loops end
+fn collect_into(
+    dir: &Path,
+    extensions: &[String],
+    recursive: bool,
+    visited: &mut HashSet<PathBuf>,
+    images: &mut Vec<PathBuf>,
+) {
+    if !visited.insert(std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())) {
+        return;
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not read directory: {}\nError: {}", dir.display(), e).red()
+            );
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                collect_into(&path, extensions, recursive, visited, images);
+            };
+            continue;
+        };
+        let matches = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+            .unwrap_or(false);
+        if matches {
+            images.push(path);
+        };
+    }
+}
+
+/// pick the index of the next wallpaper from `candidates`
+pub fn choose(
+    candidates: &[String],
+    selection: Selection,
+    cache: &SelectionCache,
+    exclude_current: bool,
+) -> Option<usize> {
+    if candidates.is_empty() {
+        return None;
+    };
+    let current = cache.current();
+    // never exclude the only image there is
+    let allowed = |index: &usize| {
+        !(exclude_current && candidates.len() > 1 && Some(&candidates[*index]) == current)
+    };
+    match selection {
+        Selection::Sequential => {
+            let start = cache.index.map(|i| i + 1).unwrap_or(0);
+            (0..candidates.len())
+                .map(|offset| (start + offset) % candidates.len())
+                .find(allowed)
+        }
+        Selection::Random => {
+            let pool = (0..candidates.len())
+                .filter(allowed)
+                .collect::<Vec<usize>>();
+            pool.get(utils::random_index(pool.len())).copied()
+        }
+        Selection::LeastRecentlyUsed => (0..candidates.len())
+            .filter(allowed)
+            .min_by_key(|index| cache.history.iter().rposition(|p| p == &candidates[*index])),
+    }
+}
+
+#[test]
+fn choose_test() {
+    let candidates = vec![
+        "a.png".to_string(),
+        "b.png".to_string(),
+        "c.png".to_string(),
+    ];
+    let mut cache = SelectionCache::default();
+    assert_eq!(
+        choose(&candidates, Selection::Sequential, &cache, false),
+        Some(0)
+    );
+    cache.record(2, "c.png");
+    assert_eq!(
+        choose(&candidates, Selection::Sequential, &cache, false),
+        Some(0)
+    );
+    cache.record(0, "a.png");
+    assert_eq!(
+        choose(&candidates, Selection::LeastRecentlyUsed, &cache, false),
+        Some(1)
+    );
+    cache.record(1, "b.png");
+    assert_eq!(
+        choose(&candidates, Selection::LeastRecentlyUsed, &cache, true),
+        Some(2)
+    );
+    let picked = choose(&candidates, Selection::Random, &cache, true).unwrap();
+    assert_ne!(candidates[picked], "b.png");
+}
+
+#[test]
+fn collect_images_test() {
+    let dir = std::env::temp_dir().join(format!("colstract-selection-{}", std::process::id()));
+    let nested = dir.join("nested");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(dir.join("a.png"), "").unwrap();
+    std::fs::write(nested.join("b.JPG"), "").unwrap();
+    std::fs::write(nested.join("notes.txt"), "").unwrap();
+    // a loop back to the top
+    std::os::unix::fs::symlink(&dir, nested.join("loop")).unwrap();
+    let extensions = vec!["png".to_string(), "jpg".to_string()];
+    let images = collect_images(&dir, &extensions, true);
+    assert_eq!(images, vec![dir.join("a.png"), nested.join("b.JPG")]);
+
+    let mut cache = SelectionCache::default();
+    cache.record(1, "b.JPG");
+    cache.save(&dir, &nested);
+    assert_eq!(SelectionCache::load(&dir, &nested).index, Some(1));
+    assert_eq!(SelectionCache::load(&dir, &dir).index, None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
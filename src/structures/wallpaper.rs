@@ -1,46 +1,208 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use colordata::traits::*;
 use serde::Deserialize;
 use serde::Serialize;
 use text_colorizer::Colorize;
 
+use crate::recolor;
+use crate::structures::selection;
+use crate::structures::Backend;
+use crate::structures::Colors;
+use crate::structures::Selection;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Hash)]
 pub struct Wallpaper {
     pub enable: Option<bool>,
     pub path: Option<String>,
     pub command: Option<Vec<String>>,
+    pub backend: Option<String>,
+    pub selection: Option<String>,
+    pub extensions: Option<Vec<String>>,
+    pub recursive: Option<bool>,
+    pub exclude_current: Option<bool>,
+    pub recolor: Option<String>,
 }
 
 impl Wallpaper {
-    pub fn apply_wallpaper(&self) {
+    /// expand the placeholders in the command
+    /// {path} - the wallpaper path, {dir} - the directory containing it,
+    /// {background}, {foreground}, {cursor}, {color0} .. {color15} - colors in hex
+    pub fn expand_command(&self, colors: Option<&Colors>) -> Option<Vec<String>> {
+        let mut placeholders: BTreeMap<String, String> = BTreeMap::new();
+        if let Some(path) = &self.path {
+            placeholders.insert("path".to_string(), path.clone());
+            let dir = std::path::Path::new(path)
+                .parent()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+            placeholders.insert("dir".to_string(), dir);
+        };
+        if let Some(colors) = colors {
+            placeholders.insert("background".to_string(), colors.background.hex());
+            placeholders.insert("foreground".to_string(), colors.foreground.hex());
+            placeholders.insert("cursor".to_string(), colors.cursor.hex());
+            for (index, color) in colors.colors.iter().enumerate() {
+                placeholders.insert(format!("color{}", index), color.hex());
+            }
+        };
+        // a single pass, so that values are never expanded again
+        let placeholder_regex = regex::Regex::new(r"\{(\w+)\}").unwrap();
+        self.command.as_ref().map(|com| {
+            com.iter()
+                .map(|arg| {
+                    placeholder_regex
+                        .replace_all(arg, |captures: &regex::Captures| {
+                            match placeholders.get(&captures[1]) {
+                                Some(value) => value.clone(),
+                                None => captures[0].to_string(),
+                            }
+                        })
+                        .into_owned()
+                })
+                .collect()
+        })
+    }
+
+    /// the commands that display the wallpaper
+    /// an explicit `command` wins over the `backend`
+    pub fn resolve_commands(&self, colors: Option<&Colors>) -> Option<(Vec<Vec<String>>, bool)> {
+        if let Some(com) = self.expand_command(colors) {
+            return Some((vec![com], false));
+        };
+        let path = self.path.as_ref()?;
+        let backend = match self.backend.as_deref()? {
+            "auto" => match Backend::detect() {
+                Some(b) => b,
+                None => {
+                    eprintln!("{}", "Could not detect a wallpaper backend".yellow());
+                    return None;
+                }
+            },
+            name => match Backend::from_name(name) {
+                Some(b) => b,
+                None => {
+                    eprintln!("{}", format!("Unknown wallpaper backend: {}", name).red());
+                    return None;
+                }
+            },
+        };
+        Some((backend.commands(path), backend.is_daemon()))
+    }
+
+    pub fn apply_wallpaper(&self, colors: Option<&Colors>) {
         if let Some(true) = self.enable {
-            if let Some(com) = &self.command {
-                let mut process = std::process::Command::new(&com[0]);
-                process.args(&com[1..]);
-                println!("{}", format!("Running command {}", &com.join(" ")).green());
-                let output = match process.output() {
-                    Ok(o) => o,
-                    Err(e) => {
-                        eprintln!("{}", format!("Could not apply wallpaper: {}", e).red());
-                        return;
-                    }
-                };
-                if !output.stdout.is_empty() {
-                    println!(
-                        "{}",
-                        format!("Stdout: {}", String::from_utf8_lossy(&output.stdout)).green()
-                    );
-                };
-                if !output.stderr.is_empty() {
-                    println!(
-                        "{}",
-                        format!("Stderr: {}", String::from_utf8_lossy(&output.stderr)).red()
-                    );
-                };
+            if let Some((commands, detach)) = self.resolve_commands(colors) {
+                let last = commands.len().saturating_sub(1);
+                for (index, com) in commands.iter().enumerate() {
+                    run_command(com, detach && index == last);
+                }
             }
         } else {
             eprintln!("{}", "wallpaper not enabled".yellow());
         };
     }
 
+    /// when the path is a directory, pick an image from it and use that as the path
+    /// the picks are remembered in `cache_dir`, nothing is picked while the wallpaper is disabled
+    pub fn select_image(&mut self, cache_dir: &Path) {
+        if self.enable != Some(true) {
+            return;
+        };
+        let dir = match &self.path {
+            Some(p) if Path::new(p).is_dir() => std::path::PathBuf::from(p),
+            _ => return,
+        };
+        let selection = match self.selection.as_deref() {
+            None => Selection::Random,
+            Some(name) => match Selection::from_name(name) {
+                Some(s) => s,
+                None => {
+                    eprintln!(
+                        "{}",
+                        format!("Unknown wallpaper selection: {}, using random", name).yellow()
+                    );
+                    Selection::Random
+                }
+            },
+        };
+        let extensions = self.extensions.clone().unwrap_or_else(|| {
+            selection::DEFAULT_EXTENSIONS
+                .iter()
+                .map(|e| e.to_string())
+                .collect()
+        });
+        let candidates = selection::collect_images(&dir, &extensions, self.recursive == Some(true))
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<String>>();
+        let mut cache = selection::SelectionCache::load(cache_dir, &dir);
+        let exclude_current = self.exclude_current.unwrap_or(true);
+        match selection::choose(&candidates, selection, &cache, exclude_current) {
+            Some(index) => {
+                println!(
+                    "{}",
+                    format!("selected wallpaper: {}", &candidates[index]).green()
+                );
+                cache.record(index, &candidates[index]);
+                cache.save(cache_dir, &dir);
+                self.path = Some(candidates[index].clone());
+            }
+            None => {
+                eprintln!(
+                    "{}",
+                    format!("No wallpapers found in {}", dir.display()).yellow()
+                );
+            }
+        };
+    }
+
+    /// like `select_image`, but keeps the image picked last in the directory when there is one
+    pub fn reuse_image(&mut self, cache_dir: &Path) {
+        if self.enable != Some(true) {
+            return;
+        };
+        let dir = match &self.path {
+            Some(p) if Path::new(p).is_dir() => std::path::PathBuf::from(p),
+            _ => return,
+        };
+        match selection::SelectionCache::load(cache_dir, &dir).current() {
+            Some(path) if Path::new(path).is_file() => self.path = Some(path.clone()),
+            _ => self.select_image(cache_dir),
+        };
+    }
+
+    /// a copy pointing at the image recolored to the palette when `recolor` is set,
+    /// so that the source image stays the path for the next theme
+    /// the recolored image is written to `output_dir`
+    pub fn recolored(&self, colors: &Colors, output_dir: &Path) -> Wallpaper {
+        let (mode, path) = match (&self.recolor, &self.path) {
+            (Some(mode), Some(path)) => (mode, path),
+            _ => return self.clone(),
+        };
+        let mode = match recolor::Mode::from_name(mode) {
+            Some(m) => m,
+            None => {
+                eprintln!("{}", format!("Unknown recolor mode: {}", mode).red());
+                return self.clone();
+            }
+        };
+        match recolor::recolor_file(Path::new(path), colors, mode, output_dir) {
+            Ok(target) => {
+                println!("{}", format!("recolored: {}", target.display()).green());
+                Wallpaper {
+                    path: Some(target.display().to_string()),
+                    ..self.clone()
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", format!("Could not recolor wallpaper: {}", e).red());
+                self.clone()
+            }
+        }
+    }
+
     pub fn set_path(&mut self, path: &str) {
         self.path = Some(path.to_string());
     }
@@ -52,4 +214,94 @@ impl Wallpaper {
     pub fn set_command(&mut self, command: Vec<String>) {
         self.command = Some(command);
     }
+
+    pub fn set_backend(&mut self, backend: &str) {
+        self.backend = Some(backend.to_string());
+    }
+}
+
+/// run a wallpaper command, spawning it without waiting when `detach` is set
+fn run_command(com: &[String], detach: bool) {
+    if com.is_empty() {
+        return;
+    };
+    let mut process = std::process::Command::new(&com[0]);
+    process.args(&com[1..]);
+    println!("{}", format!("Running command {}", &com.join(" ")).green());
+    if detach {
+        process
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        if let Err(e) = process.spawn() {
+            eprintln!("{}", format!("Could not apply wallpaper: {}", e).red());
+        };
+        return;
+    };
+    let output = match process.output() {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", format!("Could not apply wallpaper: {}", e).red());
+            return;
+        }
+    };
+    if !output.stdout.is_empty() {
+        println!(
+            "{}",
+            format!("Stdout: {}", String::from_utf8_lossy(&output.stdout)).green()
+        );
+    };
+    if !output.stderr.is_empty() {
+        println!(
+            "{}",
+            format!("Stderr: {}", String::from_utf8_lossy(&output.stderr)).red()
+        );
+    };
+}
+
+#[test]
+fn expand_command_test() {
+    let wal = Wallpaper {
+        enable: Some(true),
+        path: Some("/home/user/Pictures/wall.png".to_string()),
+        command: Some(vec![
+            "feh".to_string(),
+            "--bg-fill".to_string(),
+            "{path}".to_string(),
+            "{dir}".to_string(),
+        ]),
+        backend: Some("feh".to_string()),
+        ..Wallpaper::default()
+    };
+    assert_eq!(
+        wal.expand_command(None).unwrap(),
+        vec![
+            "feh",
+            "--bg-fill",
+            "/home/user/Pictures/wall.png",
+            "/home/user/Pictures"
+        ]
+    );
+
+    let nord = crate::builtin::load_builtin("nord", crate::derive::Strategy::default())
+        .unwrap()
+        .colors
+        .unwrap();
+    let wal = Wallpaper {
+        path: Some("/home/user/{dir}/wall.png".to_string()),
+        command: Some(vec![
+            "{color1}{color10}".to_string(),
+            "{path}".to_string(),
+            "{unknown}".to_string(),
+        ]),
+        ..wal
+    };
+    assert_eq!(
+        wal.expand_command(Some(&nord)).unwrap(),
+        vec![
+            format!("{}{}", nord.colors[1].hex(), nord.colors[10].hex()),
+            "/home/user/{dir}/wall.png".to_string(),
+            "{unknown}".to_string()
+        ]
+    );
 }
@@ -0,0 +1,294 @@
+use std::path::PathBuf;
+
+use colordata::traits::*;
+use colordata::Color;
+use text_colorizer::Colorize;
+
+use crate::builtin;
+use crate::colorspace;
+use crate::config::Config;
+use crate::derive;
+use crate::history;
+use crate::utils;
+
+/// `colstract theme save|list|apply|remove`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeCommand {
+    Save(String),
+    List,
+    ListBuiltin,
+    Apply(String),
+    Remove(String),
+}
+
+impl ThemeCommand {
+    /// remove the theme subcommand from the arguments, if there is one
+    /// `colstract themes [--builtin]` is the same as `colstract theme list [--builtin]`
+    pub fn take(args: &mut Vec<String>) -> Option<Self> {
+        if args.get(1).map(String::as_str) == Some("themes") {
+            args.insert(2, "list".to_string());
+            args[1] = "theme".to_string();
+        };
+        if args.get(1).map(String::as_str) != Some("theme") {
+            return None;
+        };
+        if args.get(2).map(String::as_str) == Some("list")
+            && args.get(3).map(String::as_str) == Some("--builtin")
+        {
+            args.drain(1..4);
+            return Some(ThemeCommand::ListBuiltin);
+        };
+        let action = args.get(2).map(String::as_str);
+        let name = args.get(3).cloned();
+        let (command, words) = match (action, name) {
+            (Some("list"), _) => (ThemeCommand::List, 2),
+            (Some("save"), Some(n)) => (ThemeCommand::Save(n), 3),
+            (Some("apply"), Some(n)) => (ThemeCommand::Apply(n), 3),
+            (Some("remove"), Some(n)) => (ThemeCommand::Remove(n), 3),
+            _ => {
+                eprintln!(
+                    "{}",
+                    "Usage: colstract theme list [--builtin] | save <name> | apply <name> | remove <name>"
+                        .red()
+                );
+                std::process::exit(1);
+            }
+        };
+        args.drain(1..1 + words);
+        Some(command)
+    }
+}
+
+/// $XDG_CONFIG_HOME/colstract/themes
+pub fn themes_dir() -> PathBuf {
+    utils::get_config_home().join("colstract").join("themes")
+}
+
+/// letters, digits, `-`, `_` and `.`, not starting with a dot
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// the file of the theme, an error for names that would leave the themes directory
+pub fn theme_path(name: &str) -> std::io::Result<PathBuf> {
+    if !valid_name(name) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid theme name: {}", name),
+        ));
+    };
+    Ok(themes_dir().join(format!("{}.toml", name)))
+}
+
+/// the names of the saved themes, sorted
+pub fn list_themes() -> Vec<String> {
+    let mut names = match std::fs::read_dir(themes_dir()) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("toml"))
+            .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+/// a theme file holds `[colors]`, optionally `[wallpaper]` and `[variables]`
+/// None when there is no such theme, or with a warning when the file is malformed
+/// missing colors are derived with `strategy`, unless the theme sets `derive` itself
+pub fn load_theme(name: &str, strategy: derive::Strategy) -> Option<Config> {
+    let path = theme_path(name).ok().filter(|p| p.is_file())?;
+    let document = match std::fs::read_to_string(&path) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", format!("Skipping theme {}: {}", name, e).yellow());
+            return None;
+        }
+    };
+    if let Err(e) = check_theme(&document) {
+        eprintln!("{}", format!("Skipping theme {}: {}", name, e).yellow());
+        return None;
+    };
+    Some(Config::from_toml_str(&document, strategy))
+}
+
+/// what `Config::from_toml_str` would panic on in a theme file
+fn check_theme(document: &str) -> Result<(), String> {
+    let value: toml::Value = toml::from_str(document).map_err(|e| e.to_string())?;
+    let is_hex = |s: &str| match s.strip_prefix('#') {
+        Some(digits) => {
+            (digits.len() == 6 || digits.len() == 8)
+                && digits.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    };
+    let colors = value
+        .get("colors")
+        .and_then(|c| c.as_table())
+        .ok_or("there is no [colors] table")?;
+    for (key, color) in colors {
+        match color.as_str() {
+            Some(hex) if is_hex(hex) => {}
+            _ => return Err(format!("{} is not a hex color", key)),
+        };
+    }
+    for key in &["background", "foreground"] {
+        if !colors.contains_key(*key) {
+            return Err(format!("{} is missing", key));
+        };
+    }
+    let path = value.get("wallpaper").and_then(|w| w.get("path"));
+    if path.map_or(false, |p| !p.is_str()) {
+        return Err("the wallpaper path is not a string".to_string());
+    };
+    Ok(())
+}
+
+/// write the colors, the wallpaper path and the variables of `config` as a theme
+/// color16 .. color255 are written when the input set them
+pub fn save_theme(name: &str, config: &Config) -> std::io::Result<PathBuf> {
+    let colors = match &config.palette() {
+        Some(c) => c,
+        None => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "there are no colors to save",
+            ))
+        }
+    };
+    let path = theme_path(name)?;
+    let hex = |color: &Color| toml::Value::String(color.hex());
+    let mut table = toml::value::Table::new();
+    table.insert("background".to_string(), hex(&colors.background));
+    table.insert("foreground".to_string(), hex(&colors.foreground));
+    table.insert("cursor".to_string(), hex(&colors.cursor));
+    for (index, color) in colors.colors.iter().enumerate() {
+        table.insert(format!("color{}", index), hex(color));
+    }
+    for (index, color) in config.extended.iter().flatten() {
+        table.insert(format!("color{}", index), hex(color));
+    }
+    let mut document = toml::value::Table::new();
+    document.insert("colors".to_string(), toml::Value::Table(table));
+    if let Some(path) = config.wallpaper.as_ref().and_then(|w| w.path.as_ref()) {
+        let mut wallpaper = toml::value::Table::new();
+        wallpaper.insert("path".to_string(), toml::Value::String(path.clone()));
+        document.insert("wallpaper".to_string(), toml::Value::Table(wallpaper));
+    };
+    if let Some(variables) = &config.variables {
+        let variables = variables
+            .iter()
+            .map(|(key, value)| (key.clone(), toml::Value::String(value.clone())))
+            .collect::<toml::value::Table>();
+        document.insert("variables".to_string(), toml::Value::Table(variables));
+    };
+    let document = toml::to_string(&toml::Value::Table(document))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::create_dir_all(themes_dir())?;
+    std::fs::write(&path, document)?;
+    Ok(path)
+}
+
+pub fn remove_theme(name: &str) -> std::io::Result<()> {
+    std::fs::remove_file(theme_path(name)?)
+}
+
+/// the saved themes with a row of swatches each
+pub fn print_themes() {
+    let names = list_themes();
+    if names.is_empty() {
+        eprintln!(
+            "{}",
+            format!("No themes in {}", themes_dir().display()).yellow()
+        );
+        return;
+    };
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    for name in names {
+        let preview = load_theme(&name, derive::Strategy::default())
+            .and_then(|c| c.colors)
+            .map(|c| history::swatches(&c))
+            .unwrap_or_default();
+        println!("{:width$}  {}", name, preview, width = width);
+    }
+}
+
+/// `random` or `random:<filter>`, and not a file that happens to start with "random"
+pub fn is_random(input: &str) -> bool {
+    input == "random" || input.starts_with("random:")
+}
+
+/// pick a saved or built-in scheme at random
+/// `filter` is "random", "random:dark" or "random:light"; `current` is the theme applied now
+/// returns the input to use, eg. "builtin:nord"
+pub fn random_theme(filter: &str, current: Option<&Config>) -> Option<String> {
+    let brightness = filter.strip_prefix("random").unwrap_or("");
+    let wanted_dark = match brightness {
+        "" => None,
+        ":dark" => Some(true),
+        ":light" => Some(false),
+        other => {
+            eprintln!(
+                "{}",
+                format!("Unknown random filter: {}, use :dark or :light", other).yellow()
+            );
+            None
+        }
+    };
+    let saved = list_themes().into_iter().filter_map(|name| {
+        load_theme(&name, derive::Strategy::default()).map(|c| (format!("theme:{}", name), c))
+    });
+    let shipped = builtin::SCHEMES
+        .iter()
+        .map(|(name, document)| (format!("builtin:{}", name), Config::from(*document)));
+    let candidates = saved
+        .chain(shipped)
+        .filter(|(_, config)| match (&config.colors, wanted_dark) {
+            (Some(colors), Some(dark)) => colorspace::is_dark(colors) == dark,
+            (Some(_), None) => true,
+            (None, _) => false,
+        })
+        .filter(|(source, config)| match current {
+            Some(c) => c.input.as_ref() != Some(source) && c.colors != config.colors,
+            None => true,
+        })
+        .map(|(source, _)| source)
+        .collect::<Vec<String>>();
+    if candidates.is_empty() {
+        return None;
+    };
+    Some(candidates[utils::random_index(candidates.len())].clone())
+}
+
+#[test]
+fn theme_file_test() {
+    assert!(valid_name("nord-dark_2.1"));
+    assert!(!valid_name("../nord"));
+    assert!(!valid_name("themes/nord"));
+    assert!(!valid_name(".hidden"));
+    assert!(!valid_name(""));
+    assert!(theme_path("../nord").is_err());
+
+    let colors = (0..16)
+        .map(|i| format!("color{} = \"#{:06x}\"\n", i, i))
+        .collect::<String>();
+    let theme = format!(
+        "[colors]\nbackground = \"#000000\"\nforeground = \"#ffffff\"\n{}",
+        colors
+    );
+    assert!(check_theme(&theme).is_ok());
+    assert!(check_theme("[colors]\nbackground = \"#000000\"").is_err());
+    assert!(check_theme(&theme.replace("#000000", "black")).is_err());
+    assert!(check_theme(&format!("{}\n[wallpaper]\npath = 3\n", theme)).is_err());
+    assert!(check_theme("colors = [").is_err());
+
+    assert!(is_random("random"));
+    assert!(is_random("random:dark"));
+    assert!(!is_random("random-walls.Xresources"));
+}
@@ -1,3 +1,9 @@
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use text_colorizer::Colorize;
+
 /// detect if the string is toml, xresources or something else
 /// 0 - toml document
 /// 1 - Xresources document
@@ -15,3 +21,183 @@ pub fn detect_string_type(s: &str) -> u8 {
         }
     }
 }
+
+/// $XDG_CONFIG_HOME or ~/.config
+pub fn get_config_home() -> PathBuf {
+    let home = match std::env::var("HOME") {
+        Ok(val) => PathBuf::from(val),
+        Err(_) => {
+            eprintln!("{}", "Could not find home directory".red());
+            eprintln!("{}", "Cannot continue, exiting".red());
+            std::process::exit(1);
+        }
+    };
+    match std::env::var("XDG_CONFIG_HOME") {
+        Ok(val) => PathBuf::from(val),
+        Err(_) => home.join(".config"),
+    }
+}
+
+/// options that are followed by one value, e.g. `--input ~/.Xresources`
+const WORD_OPTIONS: [&str; 16] = [
+    "--config",
+    "-c",
+    "--input",
+    "-i",
+    "--theme",
+    "-t",
+    "--output",
+    "-o",
+    "--wallpaper",
+    "-w",
+    "--recolor",
+    "--min-contrast",
+    "--adjust",
+    "--style",
+    "--size",
+    "--colors",
+];
+/// options that take every argument up to the next option, e.g. `-W swww img`
+const VECTOR_OPTIONS: [&str; 2] = ["--wallpaper-command", "-W"];
+
+/// remove a boolean switch (e.g. `--no-sequences`) from the arguments
+/// only where a flag can stand, the values of options are left alone
+/// returns true if any of the given names was present
+pub fn take_flag(args: &mut Vec<String>, names: &[&str]) -> bool {
+    let mut found = false;
+    let mut in_vector = false;
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        if WORD_OPTIONS.contains(&arg) {
+            in_vector = false;
+            index += 2;
+        } else if VECTOR_OPTIONS.contains(&arg) {
+            in_vector = true;
+            index += 1;
+        } else if !in_vector && names.contains(&arg) {
+            args.remove(index);
+            found = true;
+        } else {
+            index += 1;
+        };
+    }
+    found
+}
+
+/// remove an option and its value (e.g. `--size 1920x1080`) from the arguments
+/// returns the value of the last occurrence
+pub fn take_option(args: &mut Vec<String>, names: &[&str]) -> Option<String> {
+    let mut value = None;
+    while let Some(position) = args.iter().position(|arg| names.contains(&arg.as_str())) {
+        args.remove(position);
+        if position < args.len() {
+            value = Some(args.remove(position));
+        };
+    }
+    value
+}
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// whether `--verbose` was given
+pub fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// find an executable in $PATH
+pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// a pseudo random index in 0..len, seeded from the clock and the process id
+/// returns 0 when len is 0
+pub fn random_index(len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    };
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    // xorshift64*
+    let mut x = (nanos ^ ((std::process::id() as u64) << 32)) | 1;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    (x.wrapping_mul(0x2545_f491_4f6c_dd1d) % len as u64) as usize
+}
+
+/// format seconds since the unix epoch as "YYYY-MM-DD HH:MM" in UTC
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+#[test]
+fn take_flag_test() {
+    let mut args = [
+        "colstract",
+        "-n",
+        "-i",
+        "-l",
+        "-W",
+        "swww",
+        "img",
+        "-n",
+        "-o",
+        "out",
+        "-l",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<String>>();
+    assert!(take_flag(&mut args, &["--no-sequences", "-n"]));
+    assert!(take_flag(&mut args, &["--light", "-l"]));
+    assert!(!take_flag(&mut args, &["--verbose", "-v"]));
+    assert_eq!(
+        args,
+        vec![
+            "colstract",
+            "-i",
+            "-l",
+            "-W",
+            "swww",
+            "img",
+            "-n",
+            "-o",
+            "out"
+        ]
+    );
+}
+
+#[test]
+fn format_timestamp_test() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+    assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13");
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use inotify::Inotify;
+use inotify::WatchDescriptor;
+use inotify::WatchMask;
+use text_colorizer::Colorize;
+
+/// how long to wait for a burst of events to settle
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// watch `files` and `directories` and call `on_change` with the paths that changed
+/// files are watched through their parent directory, so that editors
+/// replacing the file on save are noticed as well
+pub fn watch<F: FnMut(&[PathBuf])>(files: &[PathBuf], directories: &[PathBuf], mut on_change: F) {
+    let mut inotify = match Inotify::init() {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", format!("Could not start watching: {}", e).red());
+            return;
+        }
+    };
+    let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE | WatchMask::DELETE;
+
+    let mut watched: Vec<(WatchDescriptor, PathBuf)> = Vec::new();
+    let parents = files.iter().map(|f| parent_dir(f.as_path()));
+    for dir in directories.iter().cloned().chain(parents) {
+        if watched.iter().any(|(_, d)| d == &dir) {
+            continue;
+        };
+        match inotify.add_watch(&dir, mask) {
+            Ok(wd) => {
+                println!("{}", format!("watching: {}", dir.display()).green());
+                watched.push((wd, dir));
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!("Could not watch {}: {}", dir.display(), e).yellow()
+                );
+            }
+        };
+    }
+    if watched.is_empty() {
+        eprintln!("{}", "Nothing to watch".red());
+        return;
+    };
+
+    let mut buffer = [0u8; 4096];
+    loop {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        match inotify.read_events_blocking(&mut buffer) {
+            Ok(events) => {
+                collect_changes(events, &watched, files, directories, &mut changed);
+            }
+            Err(e) => {
+                eprintln!("{}", format!("Could not read events: {}", e).red());
+                return;
+            }
+        };
+        // keep reading until a whole quiet period passes without events
+        loop {
+            std::thread::sleep(DEBOUNCE);
+            match inotify.read_events(&mut buffer) {
+                Ok(events) => {
+                    if collect_changes(events, &watched, files, directories, &mut changed) == 0 {
+                        break;
+                    };
+                }
+                Err(_) => break,
+            };
+        }
+        if !changed.is_empty() {
+            on_change(&changed);
+        };
+    }
+}
+
+/// the directory a file is watched through, `.` for a bare file name
+fn parent_dir(file: &Path) -> PathBuf {
+    match file.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// add the changed paths to `changed`, files as they were given
+/// returns the number of events read
+fn collect_changes<'a>(
+    events: impl Iterator<Item = inotify::Event<&'a std::ffi::OsStr>>,
+    watched: &[(WatchDescriptor, PathBuf)],
+    files: &[PathBuf],
+    directories: &[PathBuf],
+    changed: &mut Vec<PathBuf>,
+) -> usize {
+    let mut count = 0;
+    for event in events {
+        count += 1;
+        let dir = match watched.iter().find(|(wd, _)| wd == &event.wd) {
+            Some((_, dir)) => dir,
+            None => continue,
+        };
+        let name = match event.name {
+            Some(name) => name,
+            None => continue,
+        };
+        let file = files
+            .iter()
+            .find(|f| &parent_dir(f) == dir && f.file_name() == Some(name));
+        let path = match file {
+            Some(f) => f.clone(),
+            None if directories.contains(dir) => dir.join(name),
+            None => continue,
+        };
+        if !changed.contains(&path) {
+            changed.push(path);
+        };
+    }
+    count
+}
+
+#[test]
+fn parent_dir_test() {
+    assert_eq!(parent_dir(Path::new("config.toml")), PathBuf::from("."));
+    assert_eq!(
+        parent_dir(Path::new("/home/user/.Xresources")),
+        PathBuf::from("/home/user")
+    );
+}